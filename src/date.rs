@@ -0,0 +1,194 @@
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use dom;
+use json_ld::JsonLdArticle;
+use markup5ever_rcdom::Handle;
+use regex::Regex;
+use url::Url;
+
+pub static URL_DATE_REGEX: &str = r"/(\d{4})[/-](\d{1,2})[/-](\d{1,2})(?:[/-]|\.html?|$)";
+static PUBLISHED_META_NAMES: [&str; 8] = [
+    "article:published_time",
+    "og:published_time",
+    "datepublished",
+    "date",
+    "pubdate",
+    "publishdate",
+    "dc.date",
+    "dcterms.created",
+];
+static MODIFIED_META_NAMES: [&str; 5] = [
+    "article:modified_time",
+    "og:updated_time",
+    "datemodified",
+    "lastmod",
+    "dcterms.modified",
+];
+lazy_static! {
+    static ref URL_DATE: Regex = Regex::new(URL_DATE_REGEX).unwrap();
+}
+
+/// Where a date was found, roughly ordered from most to least trustworthy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateSource {
+    JsonLd,
+    Meta,
+    Time,
+    Url,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArticleDate {
+    pub date: DateTime<FixedOffset>,
+    pub source: DateSource,
+}
+
+impl ArticleDate {
+    fn new(date: DateTime<FixedOffset>, source: DateSource) -> ArticleDate {
+        ArticleDate { date, source }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dates {
+    pub published: Option<ArticleDate>,
+    pub modified: Option<ArticleDate>,
+}
+
+/// Parses the date formats commonly found in article markup. Values without
+/// a timezone are treated as UTC.
+pub fn parse_date(value: &str) -> Option<DateTime<FixedOffset>> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date);
+    }
+    if let Ok(date) = DateTime::parse_from_rfc2822(value) {
+        return Some(date);
+    }
+    for format in [
+        "%Y-%m-%dT%H:%M:%S%z",
+        "%Y-%m-%dT%H:%M:%S%.f%z",
+        "%Y-%m-%d %H:%M:%S%z",
+    ]
+    .iter()
+    {
+        if let Ok(date) = DateTime::parse_from_str(value, format) {
+            return Some(date);
+        }
+    }
+    for format in [
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M:%S",
+    ]
+    .iter()
+    {
+        if let Ok(date) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(utc(date));
+        }
+    }
+    for format in ["%Y-%m-%d", "%Y/%m/%d", "%Y%m%d"].iter() {
+        if let Ok(date) = NaiveDate::parse_from_str(value, format) {
+            return date.and_hms_opt(0, 0, 0).map(utc);
+        }
+    }
+    None
+}
+
+fn utc(date: NaiveDateTime) -> DateTime<FixedOffset> {
+    Utc.from_utc_datetime(&date).fixed_offset()
+}
+
+pub fn find_dates(handle: Handle, url: &Url, article: Option<&JsonLdArticle>) -> Dates {
+    let mut dates = Dates::default();
+    if let Some(article) = article {
+        if let Some(ref date) = article.date_published {
+            set_date(&mut dates.published, date, DateSource::JsonLd);
+        }
+        if let Some(ref date) = article.date_modified {
+            set_date(&mut dates.modified, date, DateSource::JsonLd);
+        }
+    }
+    let sources: [fn(Handle, &Url, &mut Dates); 3] =
+        [find_meta_dates, find_time_dates, find_url_dates];
+    for find in sources.iter() {
+        if dates.published.is_some() && dates.modified.is_some() {
+            break;
+        }
+        find(handle.clone(), url, &mut dates);
+    }
+    dates
+}
+
+fn set_date(date: &mut Option<ArticleDate>, value: &str, source: DateSource) {
+    if date.is_none() {
+        *date = parse_date(value).map(|d| ArticleDate::new(d, source));
+    }
+}
+
+fn find_meta_dates(handle: Handle, _url: &Url, dates: &mut Dates) {
+    let mut metas = vec![];
+    dom::find_node(handle, "meta", &mut metas);
+    for meta in metas.into_iter() {
+        let content = match dom::get_attr("content", meta.clone()) {
+            Some(content) => content,
+            None => continue,
+        };
+        for attr in ["property", "name", "itemprop"].iter() {
+            let key = match dom::get_attr(attr, meta.clone()) {
+                Some(key) => key.trim().to_lowercase(),
+                None => continue,
+            };
+            if PUBLISHED_META_NAMES.contains(&key.as_ref()) {
+                set_date(&mut dates.published, &content, DateSource::Meta);
+            }
+            if MODIFIED_META_NAMES.contains(&key.as_ref()) {
+                set_date(&mut dates.modified, &content, DateSource::Meta);
+            }
+        }
+    }
+}
+
+fn find_time_dates(handle: Handle, _url: &Url, dates: &mut Dates) {
+    let mut times = vec![];
+    dom::find_node(handle, "time", &mut times);
+    let mut first = None;
+    for time in times.into_iter() {
+        let datetime = match dom::get_attr("datetime", time.clone()) {
+            Some(datetime) => datetime,
+            None => continue,
+        };
+        let itemprop = dom::get_attr("itemprop", time.clone()).unwrap_or_default();
+        if itemprop.eq_ignore_ascii_case("dateModified") {
+            set_date(&mut dates.modified, &datetime, DateSource::Time);
+        } else if itemprop.eq_ignore_ascii_case("datePublished")
+            || dom::get_attr("pubdate", time.clone()).is_some()
+        {
+            set_date(&mut dates.published, &datetime, DateSource::Time);
+        } else if first.is_none() {
+            first = Some(datetime);
+        }
+    }
+    if let Some(datetime) = first {
+        set_date(&mut dates.published, &datetime, DateSource::Time);
+    }
+}
+
+fn find_url_dates(_handle: Handle, url: &Url, dates: &mut Dates) {
+    if dates.published.is_some() {
+        return;
+    }
+    if let Some(captures) = URL_DATE.captures(url.path()) {
+        let date = NaiveDate::from_ymd_opt(
+            captures[1].parse().unwrap_or(0),
+            captures[2].parse().unwrap_or(0),
+            captures[3].parse().unwrap_or(0),
+        );
+        dates.published = date
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .map(|d| ArticleDate::new(utc(d), DateSource::Url));
+    }
+}
@@ -1,5 +1,6 @@
 use html5ever::tendril::StrTendril;
 use html5ever::Attribute;
+use html5ever::{LocalName, QualName};
 use markup5ever_rcdom::NodeData::{Element, Text};
 use markup5ever_rcdom::{Handle, Node};
 use std::rc::Rc;
@@ -30,22 +31,75 @@ pub fn attr(attr_name: &str, attrs: &[Attribute]) -> Option<String> {
     None
 }
 
+pub fn get_parent(handle: Handle) -> Option<Handle> {
+    let parent = handle.parent.take();
+    handle.parent.set(parent.clone());
+    parent.and_then(|p| p.upgrade())
+}
+
+pub fn get_inherited_attr(name: &str, handle: Handle) -> Option<String> {
+    let mut node = Some(handle);
+    while let Some(n) = node {
+        if let Some(value) = get_attr(name, n.clone()) {
+            if !value.trim().is_empty() {
+                return Some(value.trim().to_string());
+            }
+        }
+        node = get_parent(n);
+    }
+    None
+}
+
+pub fn is_visible(handle: Handle) -> bool {
+    if let Element { ref attrs, .. } = handle.data {
+        let attrs = attrs.borrow();
+        if attr("hidden", &attrs).is_some() {
+            return false;
+        }
+        if let Some(style) = attr("style", &attrs) {
+            let style = style.to_lowercase().replace(' ', "");
+            if style.contains("display:none") || style.contains("visibility:hidden") {
+                return false;
+            }
+        }
+        // math fallback images are hidden from screen readers only and
+        // still carry the rendered formula
+        if attr("aria-hidden", &attrs)
+            .map(|v| v.trim() == "true")
+            .unwrap_or(false)
+            && !attr("class", &attrs)
+                .map(|v| v.contains("fallback-image"))
+                .unwrap_or(false)
+        {
+            return false;
+        }
+    }
+    true
+}
+
 pub fn set_attr(attr_name: &str, value: &str, handle: Handle) {
     if let Element {
         name: _, ref attrs, ..
     } = handle.data
     {
         let attrs = &mut attrs.borrow_mut();
+        let value = match StrTendril::from_str(value) {
+            Ok(value) => value,
+            Err(_) => return,
+        };
         if let Some(index) = attrs.iter().position(|attr| {
             let name = attr.name.local.as_ref();
             name == attr_name
         }) {
-            if let Ok(value) = StrTendril::from_str(value) {
-                attrs[index] = Attribute {
-                    name: attrs[index].name.clone(),
-                    value,
-                }
+            attrs[index] = Attribute {
+                name: attrs[index].name.clone(),
+                value,
             }
+        } else {
+            attrs.push(Attribute {
+                name: QualName::new(None, ns!(), LocalName::from(attr_name)),
+                value,
+            })
         }
     }
 }
@@ -59,6 +113,7 @@ pub fn clean_attr(attr_name: &str, attrs: &mut Vec<Attribute>) {
     }
 }
 
+#[allow(clippy::collapsible_match)]
 pub fn is_empty(handle: Handle) -> bool {
     for child in handle.children.borrow().iter() {
         let c = child.clone();
@@ -100,6 +155,7 @@ pub fn has_link(handle: Handle) -> bool {
     false
 }
 
+#[allow(clippy::collapsible_match)]
 pub fn extract_text(handle: Handle, text: &mut String, deep: bool) {
     for child in handle.children.borrow().iter() {
         let c = child.clone();
@@ -147,6 +203,7 @@ pub fn find_node(handle: Handle, tag_name: &str, nodes: &mut Vec<Rc<Node>>) {
     }
 }
 
+#[allow(clippy::manual_contains)]
 pub fn has_nodes(handle: Handle, tag_names: &Vec<&'static str>) -> bool {
     for child in handle.children.borrow().iter() {
         let tag_name: &str = &get_tag_name(child.clone()).unwrap_or_default();
@@ -176,3 +233,7 @@ pub fn text_children_count(handle: Handle) -> usize {
     }
     count
 }
+
+pub fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
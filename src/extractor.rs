@@ -1,8 +1,17 @@
+use date;
+use date::ArticleDate;
 use dom;
 use error::Error;
+use html5ever::serialize::{SerializeOpts, TraversalScope};
 use html5ever::tendril::stream::TendrilSink;
-use html5ever::{parse_document, serialize};
-use markup5ever_rcdom::{RcDom, SerializableHandle};
+use html5ever::tree_builder::{ElementFlags, NodeOrText, TreeSink};
+use html5ever::{parse_document, serialize, LocalName, QualName};
+use json_ld;
+use markup5ever_rcdom::NodeData::Element;
+use markup5ever_rcdom::{Handle, RcDom, SerializableHandle};
+use metadata::Metadata;
+use options::ExtractorOptions;
+use regex::Regex;
 #[cfg(feature = "reqwest")]
 use reqwest;
 use scorer;
@@ -12,15 +21,59 @@ use std::collections::BTreeMap;
 use std::default::Default;
 use std::io::Read;
 use std::path::Path;
-#[cfg(feature = "reqwest")]
+use std::rc::Rc;
 use std::time::Duration;
 use url::Url;
 
+pub static TITLE_SEPARATOR_REGEX: &str = r" [\|\-\\/>»] ";
+pub static TITLE_HIERARCHICAL_SEPARATOR_REGEX: &str = r" [\\/>»] ";
+static TITLE_MIN_WORDS: usize = 3;
+static TITLE_MAX_PREFIX_WORDS: usize = 5;
+static TITLE_SHORT_WORDS: usize = 4;
+static EXCERPT_MIN_PARAGRAPH_LENGTH: usize = 80;
+static SENTENCE_TERMINATORS: [char; 6] = ['.', '!', '?', '。', '！', '？'];
+lazy_static! {
+    static ref TITLE_SEPARATOR: Regex = Regex::new(TITLE_SEPARATOR_REGEX).unwrap();
+    static ref TITLE_HIERARCHICAL_SEPARATOR: Regex =
+        Regex::new(TITLE_HIERARCHICAL_SEPARATOR_REGEX).unwrap();
+    static ref TITLE_SEPARATORS: Regex = Regex::new(r"[\|\-\\/>»]+").unwrap();
+}
+
 #[derive(Debug)]
 pub struct Product {
     pub title: String,
+    pub byline: Option<String>,
+    pub excerpt: Option<String>,
+    pub lang: Option<String>,
+    pub dir: Option<String>,
+    pub published: Option<ArticleDate>,
+    pub modified: Option<ArticleDate>,
+    pub lead_image: Option<String>,
+    pub canonical_url: Option<String>,
+    pub amp_url: Option<String>,
+    pub site_name: Option<String>,
+    pub metadata: Metadata,
     pub content: String,
     pub text: String,
+    pub word_count: usize,
+    pub char_count: usize,
+    pub cjk_char_count: usize,
+    pub reading_time: Duration,
+}
+
+impl Product {
+    pub fn estimate_reading_time(
+        &self,
+        words_per_minute: usize,
+        cjk_chars_per_minute: usize,
+    ) -> Duration {
+        estimate_reading_time(
+            self.word_count,
+            self.cjk_char_count,
+            words_per_minute,
+            cjk_chars_per_minute,
+        )
+    }
 }
 
 #[cfg(feature = "reqwest")]
@@ -38,6 +91,60 @@ pub fn scrape(url: &str) -> Result<Product, Error> {
 }
 
 pub fn extract<R>(input: &mut R, url: &Url) -> Result<Product, Error>
+where
+    R: Read,
+{
+    extract_with_options(input, url, &ExtractorOptions::default())
+}
+
+pub fn extract_with_options<R>(
+    input: &mut R,
+    url: &Url,
+    options: &ExtractorOptions,
+) -> Result<Product, Error>
+where
+    R: Read,
+{
+    let mut html = vec![];
+    input.read_to_end(&mut html)?;
+    let mut best: Option<Product> = None;
+    for options in relaxed_options(options).iter() {
+        let product = extract_once(&mut html.as_slice(), url, options)?;
+        if product.char_count >= options.char_threshold {
+            return Ok(product);
+        }
+        if best
+            .as_ref()
+            .map(|b| product.char_count > b.char_count)
+            .unwrap_or(true)
+        {
+            best = Some(product);
+        }
+    }
+    best.ok_or(Error::Unexpected)
+}
+
+// Each attempt disables one more heuristic, as Mozilla's readability does
+// when the extracted text is too short.
+fn relaxed_options(options: &ExtractorOptions) -> Vec<ExtractorOptions> {
+    let mut attempts = vec![options.clone()];
+    let mut relaxed = options.clone();
+    if relaxed.strip_unlikely_candidates {
+        relaxed.strip_unlikely_candidates = false;
+        attempts.push(relaxed.clone());
+    }
+    if relaxed.weight_classes {
+        relaxed.weight_classes = false;
+        attempts.push(relaxed.clone());
+    }
+    if relaxed.clean_conditionally {
+        relaxed.clean_conditionally = false;
+        attempts.push(relaxed.clone());
+    }
+    attempts
+}
+
+fn extract_once<R>(input: &mut R, url: &Url, options: &ExtractorOptions) -> Result<Product, Error>
 where
     R: Read,
 {
@@ -45,43 +152,364 @@ where
         .from_utf8()
         .read_from(input)?;
     let mut title = String::new();
+    let mut byline = None;
+    let mut metadata = Metadata::default();
     let mut candidates = BTreeMap::new();
     let mut nodes = BTreeMap::new();
     let handle = dom.document.clone();
-    scorer::preprocess(&mut dom, handle.clone(), &mut title);
-    scorer::find_candidates(Path::new("/"), handle.clone(), &mut candidates, &mut nodes);
-    let mut id: &str = "/";
-    let mut top_candidate: &Candidate = &Candidate {
+    metadata.json_ld = json_ld::find_article(handle.clone());
+    let dates = date::find_dates(handle.clone(), url, metadata.json_ld.as_ref());
+    scorer::preprocess(
+        &mut dom,
+        handle.clone(),
+        &mut title,
+        &mut byline,
+        &mut metadata,
+        options,
+    );
+    if let Some(article) = metadata.json_ld.as_ref() {
+        if let Some(ref headline) = article.headline {
+            title = headline.clone();
+        }
+        if let Some(author) = article.byline() {
+            byline = Some(author);
+        }
+    }
+    if metadata
+        .json_ld
+        .as_ref()
+        .and_then(|a| a.headline.as_ref())
+        .is_none()
+    {
+        let mut h1s = vec![];
+        dom::find_node(handle.clone(), "h1", &mut h1s);
+        let h1s = h1s
+            .into_iter()
+            .map(|h1| {
+                let mut text = String::new();
+                dom::extract_text(h1, &mut text, true);
+                text
+            })
+            .collect::<Vec<_>>();
+        title = clean_title(&title, &h1s, metadata.open_graph.title.as_deref());
+    }
+    if title.trim().is_empty() {
+        title = metadata.title().unwrap_or_default().to_string();
+    }
+    if byline.is_none() {
+        byline = metadata.author.clone();
+    }
+    scorer::find_candidates(
+        Path::new("/"),
+        handle.clone(),
+        &mut candidates,
+        &mut nodes,
+        options,
+    );
+    let top_id = scorer::find_top_candidate(&mut candidates, &nodes, options);
+    let document = Candidate {
         node: handle.clone(),
         score: Cell::new(0.0),
     };
-    for (i, c) in candidates.iter() {
-        let score = c.score.get() * (1.0 - scorer::get_link_density(c.node.clone()));
-        c.score.set(score);
-        if score <= top_candidate.score.get() {
-            continue;
-        }
-        id = i;
-        top_candidate = c;
-    }
+    let (id, top_candidate) = match top_id
+        .as_ref()
+        .and_then(|id| candidates.get(id).map(|c| (id.as_str(), c)))
+    {
+        Some((id, c)) => (id, c),
+        None => ("/", &document),
+    };
     let mut bytes = vec![];
+    let base_url = metadata.base_url(url);
 
-    let node = top_candidate.node.clone();
-    scorer::clean(&mut dom, Path::new(id), node.clone(), url, &candidates);
+    let top_node = top_candidate.node.clone();
+    // Without a candidate the top node is the document itself, which has no
+    // attributes, so `lang` and `dir` are read from the `html` element.
+    let attr_node = match top_node.data {
+        Element { .. } => top_node.clone(),
+        _ => {
+            let mut html = vec![];
+            dom::find_node(handle.clone(), "html", &mut html);
+            html.into_iter().next().unwrap_or_else(|| top_node.clone())
+        }
+    };
+    let lang = dom::get_inherited_attr("lang", attr_node.clone()).or_else(|| metadata.lang());
+    let dir = dom::get_inherited_attr("dir", attr_node).map(|d| d.to_lowercase());
+    let siblings =
+        scorer::find_article_siblings(Path::new(id), top_candidate, &candidates, options);
+    let mut article_nodes = vec![];
+    for (sid, sibling) in siblings.into_iter() {
+        let useless = scorer::clean(
+            &mut dom,
+            sid.as_path(),
+            sibling.clone(),
+            &base_url,
+            url,
+            &candidates,
+            options,
+        );
+        if !useless || Rc::ptr_eq(&sibling, &top_node) {
+            article_nodes.push(sibling);
+        }
+    }
+    let node = if article_nodes.len() > 1 {
+        let name = QualName::new(None, ns!(), LocalName::from("div"));
+        let container = dom.create_element(name, vec![], ElementFlags::default());
+        for article_node in article_nodes.iter() {
+            dom.remove_from_parent(article_node);
+            dom.append(&container, NodeOrText::AppendNode(article_node.clone()));
+        }
+        container
+    } else {
+        top_node
+    };
+    if let Some(ref dir) = dir {
+        dom::set_attr("dir", dir, node.clone());
+    }
 
+    // Keep the root element so that attributes such as `dir` survive.
+    let traversal_scope = match node.data {
+        Element { .. } => TraversalScope::IncludeNode,
+        _ => TraversalScope::ChildrenOnly(None),
+    };
     serialize(
         &mut bytes,
         &SerializableHandle::from(node.clone()),
-        Default::default(),
+        SerializeOpts {
+            traversal_scope,
+            ..Default::default()
+        },
     )
     .ok();
     let content = String::from_utf8(bytes).unwrap_or_default();
 
+    let canonical = metadata
+        .canonical
+        .as_ref()
+        .or(metadata.open_graph.url.as_ref())
+        .and_then(|canonical| base_url.join(canonical).ok())
+        .filter(|canonical| canonical.scheme() == "http" || canonical.scheme() == "https");
+    let amp_url = metadata
+        .amphtml
+        .as_ref()
+        .and_then(|amp| base_url.join(amp).ok())
+        .map(|amp| amp.to_string());
+    let site_name = metadata
+        .open_graph
+        .site_name
+        .clone()
+        .or_else(|| metadata.json_ld.as_ref().and_then(|a| a.publisher.clone()))
+        .or_else(|| {
+            canonical
+                .as_ref()
+                .unwrap_or(url)
+                .host_str()
+                .map(|host| host.trim_start_matches("www.").to_string())
+        });
+    let canonical_url = canonical.map(|canonical| canonical.to_string());
+    let lead_image = metadata
+        .image()
+        .and_then(|image| base_url.join(image).ok())
+        .map(|image| image.to_string())
+        .or_else(|| scorer::find_lead_image(node.clone(), &base_url));
+    let excerpt = metadata
+        .description()
+        .map(|description| description.to_string())
+        .or_else(|| get_paragraph_excerpt(node.clone()))
+        .map(|excerpt| truncate_at_sentence(&excerpt, options.excerpt_length));
+
     let mut text: String = String::new();
     dom::extract_text(node.clone(), &mut text, true);
+    if text.trim().is_empty() {
+        if let Some(body) = metadata
+            .json_ld
+            .as_ref()
+            .and_then(|a| a.article_body.clone())
+        {
+            text = body;
+        }
+    }
+    let word_count = count_words(&text);
+    let char_count = text.chars().filter(|c| !c.is_whitespace()).count();
+    let cjk_char_count = count_cjk_chars(&text);
     Ok(Product {
         title,
+        byline,
+        excerpt,
+        lang,
+        dir,
+        published: dates.published,
+        modified: dates.modified,
+        lead_image,
+        canonical_url,
+        amp_url,
+        site_name,
+        metadata,
         content,
         text,
+        word_count,
+        char_count,
+        cjk_char_count,
+        reading_time: estimate_reading_time(
+            word_count,
+            cjk_char_count,
+            options.words_per_minute,
+            options.cjk_chars_per_minute,
+        ),
     })
 }
+
+pub fn clean_title(original: &str, h1s: &[String], og_title: Option<&str>) -> String {
+    let original = dom::normalize_whitespace(original);
+    let candidates = h1s
+        .iter()
+        .map(|h1| dom::normalize_whitespace(h1))
+        .chain(og_title.map(dom::normalize_whitespace))
+        .filter(|c| !c.is_empty())
+        .collect::<Vec<_>>();
+    let is_candidate = |piece: &str| candidates.iter().any(|c| c.eq_ignore_ascii_case(piece));
+    if is_candidate(&original) {
+        return original;
+    }
+    let mut title = original.clone();
+    let mut hierarchical = false;
+    let separators = TITLE_SEPARATOR.find_iter(&original).collect::<Vec<_>>();
+    if let (Some(first), Some(last)) = (separators.first(), separators.last()) {
+        if let Some(piece) = TITLE_SEPARATOR
+            .split(&original)
+            .map(|p| p.trim())
+            .find(|p| is_candidate(p))
+        {
+            return piece.to_string();
+        }
+        hierarchical = TITLE_HIERARCHICAL_SEPARATOR.is_match(&original);
+        title = original[..last.start()].trim().to_string();
+        if count_words(&title) < TITLE_MIN_WORDS {
+            title = original[first.end()..].trim().to_string();
+        }
+    } else if let (Some(first), Some(last)) = (original.find(": "), original.rfind(": ")) {
+        if let Some(piece) = original
+            .split(": ")
+            .map(|p| p.trim())
+            .find(|p| is_candidate(p))
+        {
+            return piece.to_string();
+        }
+        title = original[last + 2..].trim().to_string();
+        if count_words(&title) < TITLE_MIN_WORDS {
+            title = original[first + 2..].trim().to_string();
+        } else if count_words(&original[..first]) > TITLE_MAX_PREFIX_WORDS {
+            title = original.clone();
+        }
+    } else if (original.chars().count() > 150 || original.chars().count() < 15) && h1s.len() == 1 {
+        title = dom::normalize_whitespace(&h1s[0]);
+    }
+    let count = count_words(&title);
+    if count <= TITLE_SHORT_WORDS
+        && (!hierarchical || count + 1 != count_words(&TITLE_SEPARATORS.replace_all(&original, "")))
+    {
+        title = original;
+    }
+    title
+}
+
+fn get_paragraph_excerpt(handle: Handle) -> Option<String> {
+    let mut paragraphs = vec![];
+    dom::find_node(handle, "p", &mut paragraphs);
+    let paragraphs = paragraphs
+        .into_iter()
+        .map(|p| {
+            let mut text = String::new();
+            dom::extract_text(p, &mut text, true);
+            dom::normalize_whitespace(&text)
+        })
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>();
+    paragraphs
+        .iter()
+        .find(|text| text.chars().count() >= EXCERPT_MIN_PARAGRAPH_LENGTH)
+        .or_else(|| paragraphs.first())
+        .cloned()
+}
+
+pub fn truncate_at_sentence(text: &str, max_length: usize) -> String {
+    let text = dom::normalize_whitespace(text);
+    if text.chars().count() <= max_length {
+        return text;
+    }
+    let end = text
+        .char_indices()
+        .nth(max_length)
+        .map(|(i, _)| i)
+        .unwrap_or(text.len());
+    let head = &text[..end];
+    if let Some(i) = head.rfind(|c| SENTENCE_TERMINATORS.contains(&c)) {
+        let c = head[i..].chars().next().unwrap_or('.');
+        let sentence = &head[..i + c.len_utf8()];
+        if sentence.chars().count() >= max_length / 2 {
+            return sentence.to_string();
+        }
+    }
+    if text[end..].starts_with(char::is_whitespace) {
+        return format!("{}…", head);
+    }
+    match head.rfind(char::is_whitespace) {
+        Some(i) if i > 0 => format!("{}…", head[..i].trim_end()),
+        _ => format!("{}…", head),
+    }
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30ff}' // Hiragana and Katakana
+        | '\u{3400}'..='\u{4dbf}' // CJK Unified Ideographs Extension A
+        | '\u{4e00}'..='\u{9fff}' // CJK Unified Ideographs
+        | '\u{f900}'..='\u{faff}' // CJK Compatibility Ideographs
+        | '\u{ff66}'..='\u{ff9f}' // Halfwidth Katakana
+        | '\u{20000}'..='\u{2fa1f}' // CJK Unified Ideographs Extension B and later
+    )
+}
+
+/// Counts Latin words separated by whitespace, and every CJK character as
+/// a word of its own since those scripts do not separate words by spaces.
+pub fn count_words(text: &str) -> usize {
+    let mut count = 0;
+    for token in text.split_whitespace() {
+        let mut in_word = false;
+        for c in token.chars() {
+            if is_cjk(c) {
+                count += 1;
+                in_word = false;
+            } else if c.is_alphanumeric() {
+                if !in_word {
+                    count += 1;
+                }
+                in_word = true;
+            }
+        }
+    }
+    count
+}
+
+pub fn count_cjk_chars(text: &str) -> usize {
+    text.chars().filter(|&c| is_cjk(c)).count()
+}
+
+/// Estimates the reading time of `word_count` words, of which
+/// `cjk_char_count` are CJK characters read at `cjk_chars_per_minute`.
+pub fn estimate_reading_time(
+    word_count: usize,
+    cjk_char_count: usize,
+    words_per_minute: usize,
+    cjk_chars_per_minute: usize,
+) -> Duration {
+    let minutes = |count: usize, per_minute: usize| {
+        if per_minute == 0 {
+            0.0
+        } else {
+            count as f64 / per_minute as f64
+        }
+    };
+    let words = word_count.saturating_sub(cjk_char_count);
+    let minutes = minutes(words, words_per_minute) + minutes(cjk_char_count, cjk_chars_per_minute);
+    Duration::from_secs((minutes * 60.0).ceil() as u64)
+}
@@ -0,0 +1,159 @@
+use dom;
+use markup5ever_rcdom::Handle;
+use serde_json::{Map, Value};
+
+static ARTICLE_TYPES: [&str; 12] = [
+    "Article",
+    "AdvertiserContentArticle",
+    "NewsArticle",
+    "AnalysisNewsArticle",
+    "OpinionNewsArticle",
+    "ReportageNewsArticle",
+    "ReviewNewsArticle",
+    "BlogPosting",
+    "LiveBlogPosting",
+    "SocialMediaPosting",
+    "ScholarlyArticle",
+    "TechArticle",
+];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JsonLdArticle {
+    pub kind: String,
+    pub headline: Option<String>,
+    pub description: Option<String>,
+    pub authors: Vec<String>,
+    pub publisher: Option<String>,
+    pub date_published: Option<String>,
+    pub date_modified: Option<String>,
+    pub image: Option<String>,
+    pub url: Option<String>,
+    pub article_body: Option<String>,
+}
+
+impl JsonLdArticle {
+    pub fn byline(&self) -> Option<String> {
+        if self.authors.is_empty() {
+            None
+        } else {
+            Some(self.authors.join(", "))
+        }
+    }
+}
+
+pub fn is_json_ld(handle: Handle) -> bool {
+    dom::get_attr("type", handle)
+        .map(|t| t.trim().eq_ignore_ascii_case("application/ld+json"))
+        .unwrap_or(false)
+}
+
+pub fn find_article(handle: Handle) -> Option<JsonLdArticle> {
+    let mut scripts = vec![];
+    dom::find_node(handle, "script", &mut scripts);
+    scripts
+        .into_iter()
+        .filter(|script| is_json_ld(script.clone()))
+        .filter_map(|script| {
+            let mut text = String::new();
+            dom::extract_text(script, &mut text, true);
+            parse(&text)
+        })
+        .next()
+}
+
+pub fn parse(text: &str) -> Option<JsonLdArticle> {
+    let value = serde_json::from_str::<Value>(text.trim()).ok()?;
+    find_article_object(&value).map(to_article)
+}
+
+fn find_article_object(value: &Value) -> Option<&Map<String, Value>> {
+    match *value {
+        Value::Array(ref values) => values.iter().filter_map(find_article_object).next(),
+        Value::Object(ref object) => {
+            if is_article_type(object.get("@type")) {
+                return Some(object);
+            }
+            object.get("@graph").and_then(find_article_object)
+        }
+        _ => None,
+    }
+}
+
+fn is_article_type(kind: Option<&Value>) -> bool {
+    match kind {
+        Some(Value::String(kind)) => ARTICLE_TYPES.contains(&kind.as_ref()),
+        Some(Value::Array(kinds)) => kinds.iter().any(|k| is_article_type(Some(k))),
+        _ => false,
+    }
+}
+
+fn to_article(object: &Map<String, Value>) -> JsonLdArticle {
+    let kind = match object.get("@type") {
+        Some(Value::Array(kinds)) => kinds.iter().filter_map(|k| k.as_str()).next(),
+        Some(kind) => kind.as_str(),
+        None => None,
+    };
+    let mut authors = vec![];
+    if let Some(author) = object.get("author") {
+        collect_names(author, &mut authors);
+    }
+    let mut publishers = vec![];
+    if let Some(publisher) = object.get("publisher") {
+        collect_names(publisher, &mut publishers);
+    }
+    JsonLdArticle {
+        kind: kind.unwrap_or_default().to_string(),
+        headline: get_string(object, "headline").or_else(|| get_string(object, "name")),
+        description: get_string(object, "description"),
+        authors,
+        publisher: publishers.into_iter().next(),
+        date_published: get_string(object, "datePublished"),
+        date_modified: get_string(object, "dateModified"),
+        image: object.get("image").and_then(get_url),
+        url: get_string(object, "url"),
+        article_body: object
+            .get("articleBody")
+            .and_then(|v| v.as_str())
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty()),
+    }
+}
+
+fn get_string(object: &Map<String, Value>, key: &str) -> Option<String> {
+    object
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(dom::normalize_whitespace)
+        .filter(|v| !v.is_empty())
+}
+
+fn collect_names(value: &Value, names: &mut Vec<String>) {
+    match *value {
+        Value::String(ref name) => {
+            let name = dom::normalize_whitespace(name);
+            if !name.is_empty() {
+                names.push(name);
+            }
+        }
+        Value::Array(ref values) => {
+            for v in values.iter() {
+                collect_names(v, names);
+            }
+        }
+        Value::Object(ref object) => {
+            if let Some(name) = get_string(object, "name") {
+                names.push(name);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn get_url(value: &Value) -> Option<String> {
+    match *value {
+        Value::String(ref url) => Some(url.trim().to_string()).filter(|url| !url.is_empty()),
+        Value::Array(ref values) => values.iter().filter_map(get_url).next(),
+        Value::Object(ref object) => get_string(object, "url"),
+        _ => None,
+    }
+}
@@ -1,14 +1,20 @@
+extern crate chrono;
 #[macro_use]
 extern crate html5ever;
 extern crate markup5ever_rcdom;
 extern crate regex;
+extern crate serde_json;
 extern crate url;
 #[macro_use]
 extern crate lazy_static;
 #[cfg(feature = "reqwest")]
 extern crate reqwest;
 
+pub mod date;
 pub mod dom;
 pub mod error;
 pub mod extractor;
+pub mod json_ld;
+pub mod metadata;
+pub mod options;
 pub mod scorer;
@@ -0,0 +1,158 @@
+use dom;
+use json_ld::JsonLdArticle;
+use markup5ever_rcdom::Handle;
+use url::Url;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OpenGraph {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub site_name: Option<String>,
+    pub url: Option<String>,
+    pub kind: Option<String>,
+    pub locale: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TwitterCard {
+    pub card: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub site: Option<String>,
+    pub creator: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    pub description: Option<String>,
+    pub author: Option<String>,
+    pub content_language: Option<String>,
+    pub open_graph: OpenGraph,
+    pub twitter: TwitterCard,
+    pub json_ld: Option<JsonLdArticle>,
+    pub image_src: Option<String>,
+    pub canonical: Option<String>,
+    pub amphtml: Option<String>,
+    pub base: Option<String>,
+}
+
+impl Metadata {
+    pub fn add_meta(&mut self, handle: Handle) {
+        let key = match dom::get_attr("property", handle.clone())
+            .or_else(|| dom::get_attr("name", handle.clone()))
+            .or_else(|| dom::get_attr("http-equiv", handle.clone()))
+        {
+            Some(key) => key.trim().to_lowercase(),
+            None => return,
+        };
+        let content = match dom::get_attr("content", handle) {
+            Some(content) => dom::normalize_whitespace(&content),
+            None => return,
+        };
+        if content.is_empty() {
+            return;
+        }
+        let field = match key.as_ref() {
+            "description" => &mut self.description,
+            "author" => &mut self.author,
+            "content-language" => &mut self.content_language,
+            "og:title" => &mut self.open_graph.title,
+            "og:description" => &mut self.open_graph.description,
+            "og:image" | "og:image:url" | "og:image:secure_url" => &mut self.open_graph.image,
+            "og:site_name" => &mut self.open_graph.site_name,
+            "og:url" => &mut self.open_graph.url,
+            "og:type" => &mut self.open_graph.kind,
+            "og:locale" => &mut self.open_graph.locale,
+            "twitter:card" => &mut self.twitter.card,
+            "twitter:title" => &mut self.twitter.title,
+            "twitter:description" => &mut self.twitter.description,
+            "twitter:image" | "twitter:image:src" => &mut self.twitter.image,
+            "twitter:site" => &mut self.twitter.site,
+            "twitter:creator" => &mut self.twitter.creator,
+            _ => return,
+        };
+        if field.is_none() {
+            *field = Some(content);
+        }
+    }
+
+    pub fn add_link(&mut self, handle: Handle) {
+        let rel = dom::get_attr("rel", handle.clone()).unwrap_or_default();
+        let href = match dom::get_attr("href", handle) {
+            Some(href) => href.trim().to_string(),
+            None => return,
+        };
+        if href.is_empty() {
+            return;
+        }
+        for rel in rel.split_whitespace() {
+            let field = match rel.to_lowercase().as_ref() {
+                "image_src" => &mut self.image_src,
+                "canonical" => &mut self.canonical,
+                "amphtml" => &mut self.amphtml,
+                _ => continue,
+            };
+            if field.is_none() {
+                *field = Some(href.clone());
+            }
+        }
+    }
+
+    pub fn add_base(&mut self, handle: Handle) {
+        if self.base.is_some() {
+            return;
+        }
+        self.base = dom::get_attr("href", handle)
+            .map(|href| href.trim().to_string())
+            .filter(|href| !href.is_empty());
+    }
+
+    /// The document base URL, i.e. the `<base href>` resolved against the
+    /// page URL, falling back to the page URL itself.
+    pub fn base_url(&self, url: &Url) -> Url {
+        self.base
+            .as_ref()
+            .and_then(|base| url.join(base).ok())
+            .filter(|base| base.scheme() == "http" || base.scheme() == "https")
+            .unwrap_or_else(|| url.clone())
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.open_graph
+            .title
+            .as_ref()
+            .or(self.twitter.title.as_ref())
+            .map(|s| s.as_ref())
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.json_ld
+            .as_ref()
+            .and_then(|a| a.description.as_ref())
+            .or(self.description.as_ref())
+            .or(self.open_graph.description.as_ref())
+            .or(self.twitter.description.as_ref())
+            .map(|s| s.as_ref())
+    }
+
+    pub fn lang(&self) -> Option<String> {
+        self.content_language
+            .as_ref()
+            .and_then(|l| l.split(',').next())
+            .map(|l| l.trim().to_string())
+            .or_else(|| self.open_graph.locale.as_ref().map(|l| l.replace('_', "-")))
+            .filter(|l| !l.is_empty())
+    }
+
+    pub fn image(&self) -> Option<&str> {
+        self.json_ld
+            .as_ref()
+            .and_then(|a| a.image.as_ref())
+            .or(self.open_graph.image.as_ref())
+            .or(self.twitter.image.as_ref())
+            .or(self.image_src.as_ref())
+            .map(|s| s.as_ref())
+    }
+}
@@ -0,0 +1,300 @@
+use regex::Regex;
+use scorer;
+
+/// Heuristics used by the extractor. When the extracted text is shorter than
+/// `char_threshold`, extraction is retried with `strip_unlikely_candidates`,
+/// `weight_classes` and `clean_conditionally` turned off one after another.
+#[derive(Debug, Clone)]
+pub struct ExtractorOptions {
+    /// Minimum text length, in characters, for a node to be scored.
+    pub min_candidate_length: usize,
+    /// Text length, in characters, under which a conditionally cleaned node is removed.
+    pub min_content_length: usize,
+    /// Text length, in characters, under which a node with a single embed is removed.
+    pub min_embed_content_length: usize,
+    /// Ratio of link text to all text above which a conditionally cleaned node is removed.
+    pub max_link_density: f32,
+    /// Number of `li` elements tolerated above the number of paragraphs.
+    pub li_count_offset: i32,
+    /// Score added or subtracted for a positive or negative id or class.
+    pub class_weight: f32,
+    /// Number of ancestors a paragraph score is propagated to.
+    pub ancestor_levels: usize,
+    /// Number of best candidates compared for a common ancestor, at least 1.
+    pub top_candidates: usize,
+    /// Fraction of the top score an alternative candidate needs.
+    pub alternative_candidate_ratio: f32,
+    /// Number of alternatives that promote their common ancestor, at least 1.
+    pub min_alternative_candidates: usize,
+    /// Per-level divisor of the score given to ancestors above the grandparent.
+    pub ancestor_score_divisor: f32,
+    /// Fraction of the top score a sibling needs to be merged.
+    pub sibling_score_ratio: f32,
+    /// Maximum excerpt length, in characters.
+    pub excerpt_length: usize,
+    /// Reading speed for non-CJK text, in words per minute.
+    pub words_per_minute: usize,
+    /// Reading speed for CJK text, in characters per minute.
+    pub cjk_chars_per_minute: usize,
+    /// Text length, in characters, under which extraction is retried with relaxed flags.
+    pub char_threshold: usize,
+    /// Score above which a document is probably readerable.
+    pub readerable_min_score: f32,
+    /// Text length, in characters, a paragraph needs to add to the readerable score.
+    pub readerable_min_content_length: usize,
+    /// Removes unlikely candidates before scoring; the first retry turns it off.
+    pub strip_unlikely_candidates: bool,
+    /// Weights scores by id and class; the second retry turns it off.
+    pub weight_classes: bool,
+    /// Removes low quality forms, tables, lists and divs; the third retry turns it off.
+    pub clean_conditionally: bool,
+    /// Makes `#fragment` links absolute instead of replacing them by their text.
+    pub absolutize_fragment_links: bool,
+    /// Id/class patterns of nodes removed before scoring.
+    pub unlikely_candidates: Vec<Regex>,
+    /// Id/class patterns that keep an otherwise unlikely node.
+    pub likely_candidates: Vec<Regex>,
+    /// Id/class patterns that add `class_weight`.
+    pub positive_candidates: Vec<Regex>,
+    /// Id/class patterns that subtract `class_weight`.
+    pub negative_candidates: Vec<Regex>,
+}
+
+impl Default for ExtractorOptions {
+    fn default() -> ExtractorOptions {
+        ExtractorOptions {
+            min_candidate_length: 20,
+            min_content_length: 25,
+            min_embed_content_length: 35,
+            max_link_density: 0.2,
+            li_count_offset: 100,
+            class_weight: 25.0,
+            ancestor_levels: 5,
+            top_candidates: 5,
+            alternative_candidate_ratio: 0.75,
+            min_alternative_candidates: 3,
+            ancestor_score_divisor: 3.0,
+            sibling_score_ratio: 0.2,
+            excerpt_length: 300,
+            words_per_minute: 200,
+            cjk_chars_per_minute: 500,
+            char_threshold: 500,
+            readerable_min_score: 20.0,
+            readerable_min_content_length: 140,
+            strip_unlikely_candidates: true,
+            weight_classes: true,
+            clean_conditionally: true,
+            absolutize_fragment_links: true,
+            unlikely_candidates: vec![scorer::UNLIKELY.clone()],
+            likely_candidates: vec![scorer::LIKELY.clone()],
+            positive_candidates: vec![scorer::POSITIVE.clone()],
+            negative_candidates: vec![scorer::NEGATIVE.clone()],
+        }
+    }
+}
+
+impl ExtractorOptions {
+    pub fn builder() -> ExtractorOptionsBuilder {
+        ExtractorOptionsBuilder::default()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExtractorOptionsBuilder {
+    options: ExtractorOptions,
+}
+
+impl ExtractorOptionsBuilder {
+    /// Minimum text length for a node to contribute score to its ancestors.
+    pub fn min_candidate_length(mut self, length: usize) -> ExtractorOptionsBuilder {
+        self.options.min_candidate_length = length;
+        self
+    }
+
+    /// Text length under which a conditionally cleaned node without a
+    /// couple of images is removed.
+    pub fn min_content_length(mut self, length: usize) -> ExtractorOptionsBuilder {
+        self.options.min_content_length = length;
+        self
+    }
+
+    /// Text length under which a conditionally cleaned node with a single
+    /// embed is removed.
+    pub fn min_embed_content_length(mut self, length: usize) -> ExtractorOptionsBuilder {
+        self.options.min_embed_content_length = length;
+        self
+    }
+
+    /// Link density above which a conditionally cleaned node is removed.
+    pub fn max_link_density(mut self, density: f32) -> ExtractorOptionsBuilder {
+        self.options.max_link_density = density;
+        self
+    }
+
+    /// Number of `li` elements tolerated before a list-heavy node is removed.
+    pub fn li_count_offset(mut self, offset: i32) -> ExtractorOptionsBuilder {
+        self.options.li_count_offset = offset;
+        self
+    }
+
+    /// Weight added or subtracted when the id or class looks positive or
+    /// negative.
+    pub fn class_weight(mut self, weight: f32) -> ExtractorOptionsBuilder {
+        self.options.class_weight = weight;
+        self
+    }
+
+    /// Number of ancestors a paragraph's score is propagated to.
+    pub fn ancestor_levels(mut self, levels: usize) -> ExtractorOptionsBuilder {
+        self.options.ancestor_levels = levels;
+        self
+    }
+
+    /// Divisor applied per level to the score given to ancestors above the
+    /// grandparent.
+    pub fn ancestor_score_divisor(mut self, divisor: f32) -> ExtractorOptionsBuilder {
+        self.options.ancestor_score_divisor = divisor;
+        self
+    }
+
+    /// Number of best candidates compared when looking for a common ancestor;
+    /// values below 1 are treated as 1.
+    pub fn top_candidates(mut self, count: usize) -> ExtractorOptionsBuilder {
+        self.options.top_candidates = count;
+        self
+    }
+
+    /// Fraction of the top candidate's score another top candidate needs to
+    /// count as an alternative.
+    pub fn alternative_candidate_ratio(mut self, ratio: f32) -> ExtractorOptionsBuilder {
+        self.options.alternative_candidate_ratio = ratio;
+        self
+    }
+
+    /// Number of alternatives that must share an ancestor for it to replace
+    /// the top candidate; values below 1 are treated as 1.
+    pub fn min_alternative_candidates(mut self, count: usize) -> ExtractorOptionsBuilder {
+        self.options.min_alternative_candidates = count;
+        self
+    }
+
+    /// Fraction of the top candidate's score a sibling needs to be merged
+    /// into the article.
+    pub fn sibling_score_ratio(mut self, ratio: f32) -> ExtractorOptionsBuilder {
+        self.options.sibling_score_ratio = ratio;
+        self
+    }
+
+    /// Maximum excerpt length, in characters.
+    pub fn excerpt_length(mut self, length: usize) -> ExtractorOptionsBuilder {
+        self.options.excerpt_length = length;
+        self
+    }
+
+    /// Reading speed for non-CJK text, in words per minute.
+    pub fn words_per_minute(mut self, words_per_minute: usize) -> ExtractorOptionsBuilder {
+        self.options.words_per_minute = words_per_minute;
+        self
+    }
+
+    /// Reading speed used for CJK characters, which are counted one by one.
+    pub fn cjk_chars_per_minute(mut self, chars_per_minute: usize) -> ExtractorOptionsBuilder {
+        self.options.cjk_chars_per_minute = chars_per_minute;
+        self
+    }
+
+    /// Text length under which extraction is retried with relaxed heuristics.
+    pub fn char_threshold(mut self, length: usize) -> ExtractorOptionsBuilder {
+        self.options.char_threshold = length;
+        self
+    }
+
+    /// Score a document needs to be considered probably readerable.
+    pub fn readerable_min_score(mut self, score: f32) -> ExtractorOptionsBuilder {
+        self.options.readerable_min_score = score;
+        self
+    }
+
+    /// Text length a paragraph needs to count towards the readerable score.
+    pub fn readerable_min_content_length(mut self, length: usize) -> ExtractorOptionsBuilder {
+        self.options.readerable_min_content_length = length;
+        self
+    }
+
+    /// Whether unlikely candidates are removed before scoring.
+    pub fn strip_unlikely_candidates(mut self, enabled: bool) -> ExtractorOptionsBuilder {
+        self.options.strip_unlikely_candidates = enabled;
+        self
+    }
+
+    /// Whether scores are weighted by id and class.
+    pub fn weight_classes(mut self, enabled: bool) -> ExtractorOptionsBuilder {
+        self.options.weight_classes = enabled;
+        self
+    }
+
+    /// Whether low quality forms, tables, lists and divs are removed.
+    pub fn clean_conditionally(mut self, enabled: bool) -> ExtractorOptionsBuilder {
+        self.options.clean_conditionally = enabled;
+        self
+    }
+
+    /// Whether in-page `#fragment` links are made absolute against the page
+    /// URL, or replaced by their text.
+    pub fn absolutize_fragment_links(mut self, enabled: bool) -> ExtractorOptionsBuilder {
+        self.options.absolutize_fragment_links = enabled;
+        self
+    }
+
+    /// Replaces the id/class patterns of nodes removed before scoring.
+    pub fn unlikely_candidates(mut self, patterns: Vec<Regex>) -> ExtractorOptionsBuilder {
+        self.options.unlikely_candidates = patterns;
+        self
+    }
+
+    /// Adds an id/class pattern of nodes removed before scoring.
+    pub fn add_unlikely_candidate(mut self, pattern: Regex) -> ExtractorOptionsBuilder {
+        self.options.unlikely_candidates.push(pattern);
+        self
+    }
+
+    /// Replaces the id/class patterns that keep an unlikely node.
+    pub fn likely_candidates(mut self, patterns: Vec<Regex>) -> ExtractorOptionsBuilder {
+        self.options.likely_candidates = patterns;
+        self
+    }
+
+    /// Adds an id/class pattern that keeps an unlikely node.
+    pub fn add_likely_candidate(mut self, pattern: Regex) -> ExtractorOptionsBuilder {
+        self.options.likely_candidates.push(pattern);
+        self
+    }
+
+    /// Replaces the id/class patterns that add `class_weight`.
+    pub fn positive_candidates(mut self, patterns: Vec<Regex>) -> ExtractorOptionsBuilder {
+        self.options.positive_candidates = patterns;
+        self
+    }
+
+    /// Adds an id/class pattern that adds `class_weight`.
+    pub fn add_positive_candidate(mut self, pattern: Regex) -> ExtractorOptionsBuilder {
+        self.options.positive_candidates.push(pattern);
+        self
+    }
+
+    /// Replaces the id/class patterns that subtract `class_weight`.
+    pub fn negative_candidates(mut self, patterns: Vec<Regex>) -> ExtractorOptionsBuilder {
+        self.options.negative_candidates = patterns;
+        self
+    }
+
+    /// Adds an id/class pattern that subtracts `class_weight`.
+    pub fn add_negative_candidate(mut self, pattern: Regex) -> ExtractorOptionsBuilder {
+        self.options.negative_candidates.push(pattern);
+        self
+    }
+
+    pub fn build(self) -> ExtractorOptions {
+        self.options
+    }
+}
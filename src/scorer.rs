@@ -1,16 +1,20 @@
 use dom;
+use html5ever::tendril::TendrilSink;
 use html5ever::tree_builder::TreeSink;
 use html5ever::tree_builder::{ElementFlags, NodeOrText};
-use html5ever::{LocalName, QualName};
+use html5ever::{parse_fragment, LocalName, QualName};
 use markup5ever_rcdom::Handle;
 use markup5ever_rcdom::Node;
 use markup5ever_rcdom::NodeData::{Comment, Doctype, Document, ProcessingInstruction};
 use markup5ever_rcdom::NodeData::{Element, Text};
 use markup5ever_rcdom::RcDom;
+use metadata::Metadata;
+use options::ExtractorOptions;
 use regex::Regex;
 use std::cell::Cell;
+use std::cmp::Ordering;
 use std::collections::BTreeMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use url::Url;
 
@@ -28,6 +32,28 @@ pub static NEGATIVE_CANDIDATES: &str = "combx|comment|com|contact|foot|footer|fo
      |scroll|shoutbox|sidebar|sponsor|shopping\
      |tags|tool|widget|form|textfield\
      |uiScale|hidden";
+pub static UNLIKELY_IMAGES: &str = "avatar|icon|logo|pixel|tracking|spacer|sprite|badge|emoji";
+static PHRASING_TAGS: [&str; 39] = [
+    "abbr", "audio", "b", "bdo", "br", "button", "cite", "code", "data", "datalist", "dfn", "em",
+    "embed", "i", "img", "input", "kbd", "label", "mark", "math", "meter", "noscript", "object",
+    "output", "progress", "q", "ruby", "samp", "script", "select", "small", "span", "strong",
+    "sub", "sup", "textarea", "time", "var", "wbr",
+];
+static UNLIKELY_ROLES: [&str; 7] = [
+    "menu",
+    "menubar",
+    "complementary",
+    "navigation",
+    "alert",
+    "alertdialog",
+    "dialog",
+];
+static LAZY_SRC_ATTRS: [&str; 3] = ["data-src", "data-original", "data-lazy-src"];
+static LAZY_SRCSET_ATTRS: [&str; 2] = ["data-srcset", "data-lazy-srcset"];
+static SPECIAL_LINK_SCHEMES: [&str; 3] = ["mailto:", "tel:", "javascript:"];
+static PLACEHOLDER_DATA_URL_LENGTH: usize = 133;
+static BYLINE_MAX_LENGTH: usize = 100;
+static LEAD_IMAGE_MIN_SIZE: u32 = 50;
 static BLOCK_CHILD_TAGS: [&str; 10] = [
     "a",
     "blockquote",
@@ -42,10 +68,14 @@ static BLOCK_CHILD_TAGS: [&str; 10] = [
 ];
 lazy_static! {
     static ref PUNCTUATIONS: Regex = Regex::new(PUNCTUATIONS_REGEX).unwrap();
-    static ref LIKELY: Regex = Regex::new(LIKELY_CANDIDATES).unwrap();
-    static ref UNLIKELY: Regex = Regex::new(UNLIKELY_CANDIDATES).unwrap();
-    static ref POSITIVE: Regex = Regex::new(POSITIVE_CANDIDATES).unwrap();
-    static ref NEGATIVE: Regex = Regex::new(NEGATIVE_CANDIDATES).unwrap();
+    pub static ref LIKELY: Regex = Regex::new(LIKELY_CANDIDATES).unwrap();
+    pub static ref UNLIKELY: Regex = Regex::new(UNLIKELY_CANDIDATES).unwrap();
+    pub static ref POSITIVE: Regex = Regex::new(POSITIVE_CANDIDATES).unwrap();
+    pub static ref NEGATIVE: Regex = Regex::new(NEGATIVE_CANDIDATES).unwrap();
+    static ref UNLIKELY_IMAGE: Regex = Regex::new(UNLIKELY_IMAGES).unwrap();
+    static ref SENTENCE_END: Regex = Regex::new(r"\.( |$)").unwrap();
+    static ref BASE64_DATA_URL: Regex =
+        Regex::new(r"(?i)^data:\s*([^\s;,]+)\s*;\s*base64\s*,(.*)$").unwrap();
 }
 
 pub struct Candidate {
@@ -54,26 +84,156 @@ pub struct Candidate {
 }
 
 pub fn fix_img_path(handle: Handle, url: &Url) -> bool {
-    let src = dom::get_attr("src", handle.clone());
-    let s = match src {
-        Some(src) => src,
+    let has_srcset = fix_srcset(handle.clone(), url);
+    fix_url_attr("src", handle, url) || has_srcset
+}
+
+/// Makes a URL-bearing attribute absolute, returning whether it is present.
+pub fn fix_url_attr(name: &str, handle: Handle, url: &Url) -> bool {
+    let s = match dom::get_attr(name, handle.clone()) {
+        Some(s) => s,
         None => return false,
     };
-    if !s.starts_with("//") && !s.starts_with("http://") && !s.starts_with("https://") {
-        if let Ok(new_url) = url.join(&s) {
-            dom::set_attr("src", new_url.as_str(), handle)
+    if !s.starts_with("http://") && !s.starts_with("https://") {
+        if let Ok(new_url) = url.join(s.trim()) {
+            dom::set_attr(name, new_url.as_str(), handle)
         }
     }
     true
 }
 
-pub fn fix_anchor_path(handle: Handle, url: &Url) -> bool {
-    let src = dom::get_attr("href", handle.clone());
-    let s = match src {
-        Some(src) => src,
+/// Makes every candidate URL of a `srcset` attribute absolute, returning
+/// whether it is present.
+pub fn fix_srcset(handle: Handle, url: &Url) -> bool {
+    let srcset = match dom::get_attr("srcset", handle.clone()) {
+        Some(srcset) => srcset,
+        None => return false,
+    };
+    let fixed = parse_srcset(&srcset)
+        .into_iter()
+        .map(|(src, descriptor)| {
+            let src = url.join(&src).map(|u| u.to_string()).unwrap_or(src);
+            if descriptor.is_empty() {
+                src
+            } else {
+                format!("{} {}", src, descriptor)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    dom::set_attr("srcset", &fixed, handle);
+    true
+}
+
+/// Splits a `srcset` value into its URLs and descriptors. URLs may contain
+/// commas themselves, so candidates are only split on commas that follow
+/// a URL and its descriptors.
+pub fn parse_srcset(srcset: &str) -> Vec<(String, String)> {
+    let mut candidates = vec![];
+    let mut rest = srcset;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if rest.is_empty() {
+            break;
+        }
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let (src, tail) = rest.split_at(end);
+        rest = tail;
+        if src.ends_with(',') {
+            candidates.push((src.trim_end_matches(',').to_string(), String::new()));
+            continue;
+        }
+        let mut depth = 0;
+        let end = rest
+            .char_indices()
+            .find(|&(_, c)| {
+                match c {
+                    '(' => depth += 1,
+                    ')' if depth > 0 => depth -= 1,
+                    ',' if depth == 0 => return true,
+                    _ => (),
+                }
+                false
+            })
+            .map(|(i, _)| i)
+            .unwrap_or(rest.len());
+        let (descriptor, tail) = rest.split_at(end);
+        rest = tail;
+        candidates.push((src.to_string(), dom::normalize_whitespace(descriptor)));
+    }
+    candidates
+}
+
+/// Returns true for base64 data URIs too small to be anything but a
+/// lazy-loading placeholder.
+pub fn is_placeholder_data_url(src: &str) -> bool {
+    match BASE64_DATA_URL.captures(src.trim()) {
+        Some(caps) => {
+            !caps[1].eq_ignore_ascii_case("image/svg+xml")
+                && caps[2].trim().len() < PLACEHOLDER_DATA_URL_LENGTH
+        }
+        None => false,
+    }
+}
+
+fn is_lazy_class(handle: Handle) -> bool {
+    dom::get_attr("class", handle)
+        .map(|c| c.to_lowercase().contains("lazy"))
+        .unwrap_or(false)
+}
+
+/// Promotes the real URL of a lazy-loaded image into `src`/`srcset` and
+/// drops placeholder data URIs.
+pub fn fix_lazy_image(handle: Handle) {
+    let first_attr = |names: &[&str]| {
+        names
+            .iter()
+            .filter_map(|name| dom::get_attr(name, handle.clone()))
+            .map(|v| v.trim().to_string())
+            .find(|v| !v.is_empty())
+    };
+    let src = dom::get_attr("src", handle.clone()).unwrap_or_default();
+    let is_placeholder = is_placeholder_data_url(&src);
+    if is_placeholder {
+        if let Element { ref attrs, .. } = handle.data {
+            dom::clean_attr("src", &mut attrs.borrow_mut());
+        }
+    }
+    let is_lazy = is_lazy_class(handle.clone());
+    if src.trim().is_empty() || is_placeholder || is_lazy {
+        if let Some(lazy_src) = first_attr(&LAZY_SRC_ATTRS) {
+            dom::set_attr("src", &lazy_src, handle.clone());
+        }
+    }
+    let has_srcset = dom::get_attr("srcset", handle.clone())
+        .map(|v| !v.trim().is_empty())
+        .unwrap_or(false);
+    if !has_srcset || is_lazy {
+        if let Some(lazy_srcset) = first_attr(&LAZY_SRCSET_ATTRS) {
+            dom::set_attr("srcset", &lazy_srcset, handle);
+        }
+    }
+}
+
+pub fn fix_anchor_path(
+    handle: Handle,
+    url: &Url,
+    page_url: &Url,
+    options: &ExtractorOptions,
+) -> bool {
+    let s = match dom::get_attr("href", handle.clone()) {
+        Some(s) => s.trim().to_string(),
         None => return false,
     };
-    if !s.starts_with("//") && !s.starts_with("http://") && !s.starts_with("https://") {
+    if let Some(fragment) = s.strip_prefix('#') {
+        if options.absolutize_fragment_links {
+            let mut new_url = page_url.clone();
+            new_url.set_fragment(Some(fragment));
+            dom::set_attr("href", new_url.as_str(), handle)
+        }
+    } else if is_special_link(&s) {
+        // kept as they are; javascript: links are unwrapped by `clean`
+    } else if !s.starts_with("http://") && !s.starts_with("https://") {
         if let Ok(new_url) = url.join(&s) {
             dom::set_attr("href", new_url.as_str(), handle)
         }
@@ -81,6 +241,113 @@ pub fn fix_anchor_path(handle: Handle, url: &Url) -> bool {
     true
 }
 
+fn is_special_link(href: &str) -> bool {
+    let href = href.to_lowercase();
+    SPECIAL_LINK_SCHEMES
+        .iter()
+        .any(|scheme| href.starts_with(scheme))
+}
+
+/// Returns true for links that should be replaced by their text: scripted
+/// links, and in-page links when they are not made absolute.
+pub fn is_unwrappable_link(handle: Handle, options: &ExtractorOptions) -> bool {
+    if dom::get_tag_name(handle.clone()).unwrap_or_default() != "a" {
+        return false;
+    }
+    match dom::get_attr("href", handle) {
+        Some(href) => {
+            let href = href.trim();
+            href.to_lowercase().starts_with("javascript:")
+                || (href.starts_with('#') && !options.absolutize_fragment_links)
+        }
+        None => false,
+    }
+}
+
+/// Replaces a node by its children.
+pub fn unwrap_node(dom: &mut RcDom, handle: Handle) {
+    let children = handle.children.borrow().clone();
+    for child in children.into_iter() {
+        dom.remove_from_parent(&child);
+        dom.append_before_sibling(&handle, NodeOrText::AppendNode(child));
+    }
+    dom.remove_from_parent(&handle);
+}
+
+fn get_dimension(name: &str, handle: Handle) -> Option<u32> {
+    dom::get_attr(name, handle).and_then(|v| {
+        let digits: String = v
+            .trim()
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        digits.parse().ok()
+    })
+}
+
+pub fn is_lead_image_candidate(handle: Handle) -> bool {
+    let src = match dom::get_attr("src", handle.clone()) {
+        Some(src) => src,
+        None => return false,
+    };
+    if src.trim().is_empty() || src.starts_with("data:") {
+        return false;
+    }
+    if is_unlikely_image_src(&src) {
+        return false;
+    }
+    for name in ["id", "class"].iter() {
+        if let Some(val) = dom::get_attr(name, handle.clone()) {
+            if UNLIKELY_IMAGE.is_match(&val.to_lowercase()) {
+                return false;
+            }
+        }
+    }
+    let width = get_dimension("width", handle.clone());
+    let height = get_dimension("height", handle.clone());
+    !matches!(width, Some(w) if w < LEAD_IMAGE_MIN_SIZE)
+        && !matches!(height, Some(h) if h < LEAD_IMAGE_MIN_SIZE)
+}
+
+// Only whole words of the file name count, so that `silicone-bakeware.jpg`
+// is not taken for an icon.
+fn is_unlikely_image_src(src: &str) -> bool {
+    let path = src.split(['?', '#']).next().unwrap_or_default();
+    let file_name = path.rsplit('/').next().unwrap_or_default().to_lowercase();
+    file_name
+        .split(['-', '_', '.'])
+        .any(|word| UNLIKELY_IMAGES.split('|').any(|unlikely| unlikely == word))
+}
+
+pub fn find_lead_image(handle: Handle, url: &Url) -> Option<String> {
+    let mut imgs = vec![];
+    dom::find_node(handle, "img", &mut imgs);
+    let imgs = imgs
+        .into_iter()
+        .filter(|img| is_lead_image_candidate(img.clone()))
+        .collect::<Vec<_>>();
+    let area = |img: &Rc<Node>| match (
+        get_dimension("width", img.clone()),
+        get_dimension("height", img.clone()),
+    ) {
+        (Some(w), Some(h)) => Some(w as u64 * h as u64),
+        _ => None,
+    };
+    let mut lead = imgs.first().cloned();
+    let mut max_area = 0;
+    for img in imgs.iter() {
+        if let Some(a) = area(img) {
+            if a > max_area {
+                max_area = a;
+                lead = Some(img.clone());
+            }
+        }
+    }
+    lead.and_then(|img| dom::get_attr("src", img))
+        .and_then(|src| url.join(src.trim()).ok())
+        .map(|src| src.to_string())
+}
+
 pub fn get_link_density(handle: Handle) -> f32 {
     let text_length = dom::text_len(handle.clone()) as f32;
     if text_length == 0.0 {
@@ -95,9 +362,9 @@ pub fn get_link_density(handle: Handle) -> f32 {
     link_length / text_length
 }
 
-pub fn is_candidate(handle: Handle) -> bool {
+pub fn is_candidate(handle: Handle, options: &ExtractorOptions) -> bool {
     let text_len = dom::text_len(handle.clone());
-    if text_len < 20 {
+    if text_len < options.min_candidate_length {
         return false;
     }
     let n: &str = &dom::get_tag_name(handle.clone()).unwrap_or_default();
@@ -110,7 +377,7 @@ pub fn is_candidate(handle: Handle) -> bool {
     }
 }
 
-pub fn init_content_score(handle: Handle) -> f32 {
+pub fn init_content_score(handle: Handle, options: &ExtractorOptions) -> f32 {
     let tag_name = dom::get_tag_name(handle.clone()).unwrap_or_default();
     let score = match tag_name.as_ref() {
         "article" => 10.0,
@@ -120,7 +387,7 @@ pub fn init_content_score(handle: Handle) -> f32 {
         "th" => 5.0,
         _ => 0.0,
     };
-    score + get_class_weight(handle.clone())
+    score + get_class_weight(handle.clone(), options)
 }
 
 pub fn calc_content_score(handle: Handle) -> f32 {
@@ -133,19 +400,26 @@ pub fn calc_content_score(handle: Handle) -> f32 {
     score
 }
 
-pub fn get_class_weight(handle: Handle) -> f32 {
+pub fn is_match_any(patterns: &[Regex], val: &str) -> bool {
+    patterns.iter().any(|pattern| pattern.is_match(val))
+}
+
+pub fn get_class_weight(handle: Handle, options: &ExtractorOptions) -> f32 {
     let mut weight: f32 = 0.0;
+    if !options.weight_classes {
+        return weight;
+    }
     if let Element {
         name: _, ref attrs, ..
     } = handle.data
     {
         for name in ["id", "class"].iter() {
             if let Some(val) = dom::attr(name, &attrs.borrow()) {
-                if POSITIVE.is_match(&val) {
-                    weight += 25.0
+                if is_match_any(&options.positive_candidates, &val) {
+                    weight += options.class_weight
                 };
-                if NEGATIVE.is_match(&val) {
-                    weight -= 25.0
+                if is_match_any(&options.negative_candidates, &val) {
+                    weight -= options.class_weight
                 }
             }
         }
@@ -153,7 +427,88 @@ pub fn get_class_weight(handle: Handle) -> f32 {
     weight
 }
 
-pub fn preprocess(dom: &mut RcDom, handle: Handle, title: &mut String) -> bool {
+pub fn is_byline(handle: Handle) -> bool {
+    if let Element { ref attrs, .. } = handle.data {
+        let attrs = attrs.borrow();
+        let has_word = |name: &str, word: &str| {
+            dom::attr(name, &attrs)
+                .map(|v| v.split_whitespace().any(|w| w.eq_ignore_ascii_case(word)))
+                .unwrap_or(false)
+        };
+        return has_word("rel", "author")
+            || has_word("itemprop", "author")
+            || has_word("class", "byline");
+    }
+    false
+}
+
+pub fn get_byline(handle: Handle) -> Option<String> {
+    let mut text = String::new();
+    dom::extract_text(handle, &mut text, true);
+    let text = dom::normalize_whitespace(&text);
+    if text.is_empty() || text.chars().count() >= BYLINE_MAX_LENGTH {
+        return None;
+    }
+    Some(text)
+}
+
+/// Cheaply guesses whether a document has enough content for extraction
+/// without running it, returning the verdict and the accumulated score.
+pub fn is_probably_readerable(dom: &RcDom, options: &ExtractorOptions) -> (bool, f32) {
+    let mut score = 0.0;
+    add_readerable_score(dom.document.clone(), false, &mut score, options);
+    (score > options.readerable_min_score, score)
+}
+
+fn add_readerable_score(
+    handle: Handle,
+    in_list: bool,
+    score: &mut f32,
+    options: &ExtractorOptions,
+) {
+    for child in handle.children.borrow().iter() {
+        if let Element { ref name, .. } = child.data {
+            if !dom::is_visible(child.clone()) {
+                continue;
+            }
+            let tag_name = name.local.as_ref().to_lowercase();
+            match tag_name.as_ref() {
+                "p" | "pre" if !in_list => {
+                    let mut match_string = String::new();
+                    for name in ["class", "id"].iter() {
+                        if let Some(val) = dom::get_attr(name, child.clone()) {
+                            match_string.push_str(&val);
+                            match_string.push(' ');
+                        }
+                    }
+                    if is_match_any(&options.unlikely_candidates, &match_string)
+                        && !is_match_any(&options.likely_candidates, &match_string)
+                    {
+                        continue;
+                    }
+                    let text_len = dom::text_len(child.clone());
+                    if text_len >= options.readerable_min_content_length {
+                        *score +=
+                            ((text_len - options.readerable_min_content_length) as f32).sqrt();
+                    }
+                }
+                "script" | "style" | "template" => (),
+                _ => {
+                    add_readerable_score(child.clone(), in_list || tag_name == "li", score, options)
+                }
+            }
+        }
+    }
+}
+
+pub fn preprocess(
+    dom: &mut RcDom,
+    handle: Handle,
+    title: &mut String,
+    byline: &mut Option<String>,
+    metadata: &mut Metadata,
+    options: &ExtractorOptions,
+) -> bool {
     if let Element {
         ref name,
         ref attrs,
@@ -162,13 +517,37 @@ pub fn preprocess(dom: &mut RcDom, handle: Handle, title: &mut String) -> bool {
     {
         let tag_name = name.local.as_ref();
         match tag_name.to_lowercase().as_ref() {
-            "script" | "link" | "style" => return true,
+            "script" | "style" => return true,
+            "link" => {
+                metadata.add_link(handle.clone());
+                return true;
+            }
             "title" => dom::extract_text(handle.clone(), title, true),
+            "meta" => metadata.add_meta(handle.clone()),
+            "base" => metadata.add_base(handle.clone()),
             _ => (),
         }
+        if tag_name != "html" && tag_name != "body" && !dom::is_visible(handle.clone()) {
+            return true;
+        }
+        if let Some(role) = dom::attr("role", &attrs.borrow()) {
+            if UNLIKELY_ROLES.contains(&role.trim().to_lowercase().as_ref()) {
+                return true;
+            }
+        }
+        if byline.is_none() && is_byline(handle.clone()) {
+            if let Some(text) = get_byline(handle.clone()) {
+                *byline = Some(text);
+                return true;
+            }
+        }
         for name in ["id", "class"].iter() {
             if let Some(val) = dom::attr(name, &attrs.borrow()) {
-                if tag_name != "body" && UNLIKELY.is_match(&val) && !LIKELY.is_match(&val) {
+                if options.strip_unlikely_candidates
+                    && tag_name != "body"
+                    && is_match_any(&options.unlikely_candidates, &val)
+                    && !is_match_any(&options.likely_candidates, &val)
+                {
                     return true;
                 }
             }
@@ -176,10 +555,13 @@ pub fn preprocess(dom: &mut RcDom, handle: Handle, title: &mut String) -> bool {
     }
     let mut useless_nodes = vec![];
     let mut paragraph_nodes = vec![];
+    let mut div_nodes = vec![];
     let mut br_count = 0;
     for child in handle.children.borrow().iter() {
-        if preprocess(dom, child.clone(), title) {
+        if preprocess(dom, child.clone(), title, byline, metadata, options) {
             useless_nodes.push(child.clone());
+        } else if dom::get_tag_name(child.clone()).unwrap_or_default() == "div" {
+            div_nodes.push(child.clone());
         }
         let c = child.clone();
         match c.data {
@@ -204,6 +586,7 @@ pub fn preprocess(dom: &mut RcDom, handle: Handle, title: &mut String) -> bool {
     for node in useless_nodes.iter() {
         dom.remove_from_parent(node);
     }
+    unwrap_noscript_images(dom, handle.clone());
     for node in paragraph_nodes.iter() {
         let name = QualName::new(None, ns!(), LocalName::from("p"));
         let p = dom.create_element(name, vec![], ElementFlags::default());
@@ -214,61 +597,273 @@ pub fn preprocess(dom: &mut RcDom, handle: Handle, title: &mut String) -> bool {
             dom.append(&p, NodeOrText::AppendText(text))
         }
     }
+    for node in div_nodes.iter() {
+        convert_div_to_paragraph(dom, node.clone());
+    }
+    // A div without block children is turned into a paragraph by the caller,
+    // so only the inline runs of mixed divs are wrapped here.
+    if dom::get_tag_name(handle.clone()).unwrap_or_default() == "div"
+        && !has_only_phrasing_content(handle.clone())
+    {
+        wrap_phrasing_content(dom, handle.clone());
+    }
     false
 }
 
+/// Replaces a placeholder image by the real one kept in the `noscript`
+/// element that follows it.
+fn unwrap_noscript_images(dom: &mut RcDom, handle: Handle) {
+    let children = handle.children.borrow().clone();
+    let mut previous: Option<Handle> = None;
+    for child in children.into_iter() {
+        match child.data {
+            Element { ref name, .. } if name.local.as_ref() == "noscript" => {
+                let placeholder = previous
+                    .take()
+                    .and_then(|p| get_single_image(p.clone()).map(|img| (p, img)))
+                    .filter(|(_, img)| is_placeholder_image(img.clone()));
+                if let Some((placeholder, old_img)) = placeholder {
+                    if let Some(img) = get_noscript_image(dom, child.clone()) {
+                        copy_image_attrs(old_img, img.clone());
+                        dom.append_before_sibling(&placeholder, NodeOrText::AppendNode(img));
+                        dom.remove_from_parent(&placeholder);
+                        dom.remove_from_parent(&child);
+                    }
+                }
+            }
+            Element { .. } => previous = Some(child.clone()),
+            Text { ref contents } if !contents.borrow().trim().is_empty() => previous = None,
+            _ => (),
+        }
+    }
+}
+
+fn get_single_image(handle: Handle) -> Option<Handle> {
+    if dom::get_tag_name(handle.clone()).unwrap_or_default() == "img" {
+        return Some(handle);
+    }
+    let children = handle
+        .children
+        .borrow()
+        .iter()
+        .filter(|c| !is_whitespace((*c).clone()))
+        .cloned()
+        .collect::<Vec<_>>();
+    if children.len() == 1 {
+        get_single_image(children[0].clone())
+    } else {
+        None
+    }
+}
+
+fn is_placeholder_image(handle: Handle) -> bool {
+    let src = dom::get_attr("src", handle.clone()).unwrap_or_default();
+    src.trim().is_empty()
+        || is_placeholder_data_url(&src)
+        || LAZY_SRC_ATTRS
+            .iter()
+            .any(|name| dom::get_attr(name, handle.clone()).is_some())
+        || is_lazy_class(handle)
+}
+
+// Keeps the alt text and data attributes of the placeholder that the real
+// image does not have itself.
+fn copy_image_attrs(from: Handle, to: Handle) {
+    let attrs = match from.data {
+        Element { ref attrs, .. } => attrs.borrow().clone(),
+        _ => return,
+    };
+    for attr in attrs.iter() {
+        let name = attr.name.local.as_ref();
+        if (name == "alt" || name.starts_with("data-")) && dom::get_attr(name, to.clone()).is_none()
+        {
+            dom::set_attr(name, &attr.value, to.clone());
+        }
+    }
+}
+
+// With scripting enabled the parser keeps the content of `noscript` as raw
+// text, so it is parsed again as a fragment.
+fn get_noscript_image(dom: &mut RcDom, handle: Handle) -> Option<Handle> {
+    let mut html = String::new();
+    dom::extract_text(handle.clone(), &mut html, false);
+    let fragment = parse_fragment(
+        RcDom::default(),
+        Default::default(),
+        QualName::new(None, ns!(html), local_name!("body")),
+        vec![],
+    )
+    .one(html);
+    let mut images = vec![];
+    dom::find_node(handle, "img", &mut images);
+    dom::find_node(fragment.document.clone(), "img", &mut images);
+    if images.len() != 1 {
+        return None;
+    }
+    let img = images.remove(0);
+    if dom::get_attr("src", img.clone()).is_none() && dom::get_attr("srcset", img.clone()).is_none()
+    {
+        return None;
+    }
+    // detach it while the fragment is still alive
+    dom.remove_from_parent(&img);
+    Some(img)
+}
+
+pub fn is_phrasing_content(handle: Handle) -> bool {
+    match handle.data {
+        Text { .. } => true,
+        Element { ref name, .. } => {
+            let tag_name = name.local.as_ref().to_lowercase();
+            PHRASING_TAGS.contains(&tag_name.as_ref())
+                || (matches!(tag_name.as_ref(), "a" | "del" | "ins")
+                    && handle
+                        .children
+                        .borrow()
+                        .iter()
+                        .all(|c| is_phrasing_content(c.clone())))
+        }
+        _ => false,
+    }
+}
+
+fn is_whitespace(handle: Handle) -> bool {
+    match handle.data {
+        Text { ref contents } => contents.borrow().trim().is_empty(),
+        Element { ref name, .. } => name.local.as_ref().eq_ignore_ascii_case("br"),
+        _ => false,
+    }
+}
+
+fn trim_trailing_whitespace(dom: &mut RcDom, handle: Handle) {
+    loop {
+        let last = handle.children.borrow().last().cloned();
+        match last {
+            Some(last) if is_whitespace(last.clone()) => dom.remove_from_parent(&last),
+            _ => break,
+        }
+    }
+}
+
+// Wraps runs of inline content of a div into paragraphs, so that text mixed
+// with block elements is scored like the rest of the article.
+pub fn wrap_phrasing_content(dom: &mut RcDom, handle: Handle) {
+    let children = handle.children.borrow().clone();
+    let mut paragraph: Option<Handle> = None;
+    for child in children.into_iter() {
+        if is_phrasing_content(child.clone()) {
+            if let Some(ref p) = paragraph {
+                dom.remove_from_parent(&child);
+                dom.append(p, NodeOrText::AppendNode(child));
+            } else if !is_whitespace(child.clone()) {
+                let name = QualName::new(None, ns!(), LocalName::from("p"));
+                let p = dom.create_element(name, vec![], ElementFlags::default());
+                dom.append_before_sibling(&child, NodeOrText::AppendNode(p.clone()));
+                dom.remove_from_parent(&child);
+                dom.append(&p, NodeOrText::AppendNode(child));
+                paragraph = Some(p);
+            }
+        } else if let Some(p) = paragraph.take() {
+            trim_trailing_whitespace(dom, p);
+        }
+    }
+    if let Some(p) = paragraph {
+        trim_trailing_whitespace(dom, p);
+    }
+}
+
+// Element children and non-blank text, ignoring comments and whitespace.
+fn significant_children(handle: Handle) -> Vec<Handle> {
+    handle
+        .children
+        .borrow()
+        .iter()
+        .filter(|c| match c.data {
+            Element { .. } => true,
+            Text { ref contents } => !contents.borrow().trim().is_empty(),
+            _ => false,
+        })
+        .cloned()
+        .collect::<Vec<_>>()
+}
+
+// Divs holding a single paragraph, or only inline content, are paragraphs in
+// practice.
+fn convert_div_to_paragraph(dom: &mut RcDom, handle: Handle) {
+    let elements = significant_children(handle.clone());
+    if elements.len() == 1
+        && dom::get_tag_name(elements[0].clone()).unwrap_or_default() == "p"
+        && get_link_density(handle.clone()) < 0.25
+    {
+        let p = elements[0].clone();
+        dom.remove_from_parent(&p);
+        dom.append_before_sibling(&handle, NodeOrText::AppendNode(p));
+        dom.remove_from_parent(&handle);
+    } else if has_only_phrasing_content(handle.clone()) {
+        set_tag_name(dom, handle, "p");
+    }
+}
+
+fn has_only_phrasing_content(handle: Handle) -> bool {
+    let elements = significant_children(handle.clone());
+    !elements.is_empty() && elements.iter().all(|c| is_phrasing_content(c.clone()))
+}
+
+pub fn set_tag_name(dom: &mut RcDom, handle: Handle, tag_name: &str) -> Handle {
+    let attrs = match handle.data {
+        Element { ref attrs, .. } => attrs.borrow().clone(),
+        _ => vec![],
+    };
+    let name = QualName::new(None, ns!(), LocalName::from(tag_name));
+    let node = dom.create_element(name, attrs, ElementFlags::default());
+    dom.append_before_sibling(&handle, NodeOrText::AppendNode(node.clone()));
+    let children = handle.children.borrow().clone();
+    for child in children.into_iter() {
+        dom.remove_from_parent(&child);
+        dom.append(&node, NodeOrText::AppendNode(child));
+    }
+    dom.remove_from_parent(&handle);
+    node
+}
+
+/// Scores every paragraph-like node and adds its score to its ancestors.
+///
+/// The score is propagated up to `options.ancestor_levels` levels: the parent
+/// gets the whole score, the grandparent half of it, and every level above
+/// gets `score / (level * options.ancestor_score_divisor)`. The `html` element
+/// and the document itself never become candidates.
 pub fn find_candidates(
     id: &Path,
     handle: Handle,
     candidates: &mut BTreeMap<String, Candidate>,
     nodes: &mut BTreeMap<String, Rc<Node>>,
+    options: &ExtractorOptions,
 ) {
     if let Some(id) = id.to_str().map(|id| id.to_string()) {
         nodes.insert(id, handle.clone());
     }
 
-    if is_candidate(handle.clone()) {
-        let score = calc_content_score(handle.clone());
-        if let Some(c) = id
-            .parent()
-            .and_then(|pid| find_or_create_candidate(pid, candidates, nodes))
-        {
-            c.score.set(c.score.get() + score)
-        }
-        if let Some(c) = id
-            .parent()
-            .and_then(|pid| pid.parent())
-            .and_then(|gpid| find_or_create_candidate(gpid, candidates, nodes))
-        {
-            c.score.set(c.score.get() + score / 2.0)
-        }
-    }
-
-    if is_candidate(handle.clone()) {
+    if is_candidate(handle.clone(), options) {
         let score = calc_content_score(handle.clone());
-        if let Some(c) = id
-            .to_str()
-            .map(|id| id.to_string())
-            .and_then(|id| candidates.get(&id))
-        {
-            c.score.set(c.score.get() + score)
-        }
-        if let Some(c) = id
-            .parent()
-            .and_then(|pid| pid.to_str())
-            .map(|id| id.to_string())
-            .and_then(|pid| candidates.get(&pid))
-        {
-            c.score.set(c.score.get() + score)
-        }
-        if let Some(c) = id
-            .parent()
-            .and_then(|p| p.parent())
-            .and_then(|pid| pid.to_str())
-            .map(|id| id.to_string())
-            .and_then(|pid| candidates.get(&pid))
-        {
-            c.score.set(c.score.get() + score)
+        let ancestors = id.ancestors().skip(1).take(options.ancestor_levels);
+        for (level, ancestor) in ancestors.enumerate() {
+            let is_scorable = ancestor
+                .to_str()
+                .and_then(|aid| nodes.get(aid))
+                .and_then(|node| dom::get_tag_name(node.clone()))
+                .map(|tag_name| tag_name != "html")
+                .unwrap_or(false);
+            if !is_scorable {
+                continue;
+            }
+            let divisor = match level {
+                0 => 1.0,
+                1 => 2.0,
+                _ => level as f32 * options.ancestor_score_divisor,
+            };
+            if let Some(c) = find_or_create_candidate(ancestor, candidates, nodes, options) {
+                c.score.set(c.score.get() + score / divisor)
+            }
         }
     }
 
@@ -278,6 +873,7 @@ pub fn find_candidates(
             child.clone(),
             candidates,
             nodes,
+            options,
         )
     }
 }
@@ -286,6 +882,7 @@ fn find_or_create_candidate<'a>(
     id: &Path,
     candidates: &'a mut BTreeMap<String, Candidate>,
     nodes: &BTreeMap<String, Rc<Node>>,
+    options: &ExtractorOptions,
 ) -> Option<&'a Candidate> {
     if let Some(id) = id.to_str().map(|id| id.to_string()) {
         if let Some(node) = nodes.get(&id) {
@@ -294,7 +891,7 @@ fn find_or_create_candidate<'a>(
                     id.clone(),
                     Candidate {
                         node: node.clone(),
-                        score: Cell::new(init_content_score(node.clone())),
+                        score: Cell::new(init_content_score(node.clone(), options)),
                     },
                 );
             }
@@ -304,12 +901,169 @@ fn find_or_create_candidate<'a>(
     None
 }
 
+fn is_body(id: &Path, nodes: &BTreeMap<String, Rc<Node>>) -> bool {
+    id.to_str()
+        .and_then(|id| nodes.get(id))
+        .and_then(|node| dom::get_tag_name(node.clone()))
+        .map(|tag_name| tag_name == "body")
+        .unwrap_or(true)
+}
+
+fn get_score(id: &Path, candidates: &BTreeMap<String, Candidate>) -> Option<f32> {
+    id.to_str()
+        .and_then(|id| candidates.get(id))
+        .map(|c| c.score.get())
+}
+
+/// Picks the top candidate after scaling every score by its link density.
+///
+/// When at least `options.min_alternative_candidates` of the best
+/// `options.top_candidates` candidates score within
+/// `options.alternative_candidate_ratio` of the best one, their closest common
+/// ancestor below `body` is promoted instead, so that multi-section articles
+/// are kept whole. The top candidate
+/// is also replaced by an ancestor whose score is higher than the score of
+/// the level below it.
+pub fn find_top_candidate(
+    candidates: &mut BTreeMap<String, Candidate>,
+    nodes: &BTreeMap<String, Rc<Node>>,
+    options: &ExtractorOptions,
+) -> Option<String> {
+    for c in candidates.values() {
+        c.score
+            .set(c.score.get() * (1.0 - get_link_density(c.node.clone())));
+    }
+    let mut top_candidates = candidates
+        .iter()
+        .map(|(id, c)| (id.clone(), c.score.get()))
+        .filter(|&(_, score)| score > 0.0)
+        .collect::<Vec<_>>();
+    top_candidates.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+    top_candidates.truncate(options.top_candidates.max(1));
+    let (mut top_id, top_score) = match top_candidates.first() {
+        Some((id, score)) => (PathBuf::from(id), *score),
+        None => return None,
+    };
+
+    let alternatives = top_candidates
+        .iter()
+        .skip(1)
+        .filter(|&&(_, score)| score / top_score >= options.alternative_candidate_ratio)
+        .map(|(id, _)| PathBuf::from(id))
+        .collect::<Vec<_>>();
+    let min_alternatives = options.min_alternative_candidates.max(1);
+    if alternatives.len() >= min_alternatives {
+        let ancestors = top_id
+            .ancestors()
+            .skip(1)
+            .map(|a| a.to_path_buf())
+            .collect::<Vec<_>>();
+        for ancestor in ancestors.iter() {
+            if is_body(ancestor, nodes) {
+                break;
+            }
+            let count = alternatives
+                .iter()
+                .filter(|alternative| alternative.starts_with(ancestor))
+                .count();
+            if count >= min_alternatives {
+                top_id = ancestor.clone();
+                break;
+            }
+        }
+    }
+    find_or_create_candidate(&top_id, candidates, nodes, options);
+
+    let mut last_score = get_score(&top_id, candidates).unwrap_or(0.0);
+    let threshold = last_score / 3.0;
+    let ancestors = top_id
+        .ancestors()
+        .skip(1)
+        .map(|a| a.to_path_buf())
+        .collect::<Vec<_>>();
+    for ancestor in ancestors.iter() {
+        if is_body(ancestor, nodes) {
+            break;
+        }
+        let score = match get_score(ancestor, candidates) {
+            Some(score) => score,
+            None => continue,
+        };
+        if score < threshold {
+            break;
+        }
+        if score > last_score {
+            top_id = ancestor.clone();
+            break;
+        }
+        last_score = score;
+    }
+    top_id.to_str().map(|id| id.to_string())
+}
+
+pub fn find_article_siblings(
+    id: &Path,
+    top_candidate: &Candidate,
+    candidates: &BTreeMap<String, Candidate>,
+    options: &ExtractorOptions,
+) -> Vec<(PathBuf, Handle)> {
+    let top = top_candidate.node.clone();
+    let (pid, parent) = match (id.parent(), dom::get_parent(top.clone())) {
+        (Some(pid), Some(parent)) => (pid, parent),
+        _ => return vec![(id.to_path_buf(), top)],
+    };
+    let top_score = top_candidate.score.get();
+    let threshold = f32::max(10.0, top_score * options.sibling_score_ratio);
+    let class = dom::get_attr("class", top.clone()).unwrap_or_default();
+    let mut siblings = vec![];
+    for (i, sibling) in parent.children.borrow().iter().enumerate() {
+        let sid = pid.join(i.to_string());
+        if Rc::ptr_eq(sibling, &top) {
+            siblings.push((sid, sibling.clone()));
+            continue;
+        }
+        let tag_name = match dom::get_tag_name(sibling.clone()) {
+            Some(tag_name) => tag_name,
+            None => continue,
+        };
+        let mut bonus = 0.0;
+        if !class.trim().is_empty()
+            && dom::get_attr("class", sibling.clone()) == Some(class.clone())
+        {
+            bonus += top_score * options.sibling_score_ratio;
+        }
+        let score = sid
+            .to_str()
+            .and_then(|sid| candidates.get(sid))
+            .map(|c| c.score.get());
+        let append = match score {
+            Some(score) if score + bonus >= threshold => true,
+            _ if tag_name == "p" => {
+                let link_density = get_link_density(sibling.clone());
+                let mut text = String::new();
+                dom::extract_text(sibling.clone(), &mut text, true);
+                let text = dom::normalize_whitespace(&text);
+                let len = text.chars().count();
+                (len > 80 && link_density < 0.25)
+                    || (len > 0 && len < 80 && link_density == 0.0 && SENTENCE_END.is_match(&text))
+            }
+            _ => false,
+        };
+        if append {
+            siblings.push((sid, sibling.clone()));
+        }
+    }
+    siblings
+}
+
 pub fn clean(
     dom: &mut RcDom,
     id: &Path,
     handle: Handle,
     url: &Url,
+    page_url: &Url,
     candidates: &BTreeMap<String, Candidate>,
+    options: &ExtractorOptions,
 ) -> bool {
     let mut useless = false;
     match handle.data {
@@ -330,12 +1084,26 @@ pub fn clean(
             let tag_name = name.local.as_ref();
             match tag_name.to_lowercase().as_ref() {
                 "script" | "link" | "style" | "noscript" | "meta" | "h1" | "object" | "header"
-                | "footer" | "aside" => useless = true,
-                "form" | "table" | "ul" | "div" => {
-                    useless = is_useless(id, handle.clone(), candidates)
+                | "footer" | "aside" | "nav" => useless = true,
+                "form" | "table" | "ul" | "div" if options.clean_conditionally => {
+                    useless = is_useless(id, handle.clone(), candidates, options)
+                }
+                "img" => {
+                    fix_lazy_image(handle.clone());
+                    useless = !fix_img_path(handle.clone(), url)
+                }
+                "a" => useless = !fix_anchor_path(handle.clone(), url, page_url, options),
+                "source" => {
+                    fix_url_attr("src", handle.clone(), url);
+                    fix_srcset(handle.clone(), url);
+                }
+                "video" => {
+                    fix_url_attr("src", handle.clone(), url);
+                    fix_url_attr("poster", handle.clone(), url);
+                }
+                "audio" | "track" => {
+                    fix_url_attr("src", handle.clone(), url);
                 }
-                "img" => useless = !fix_img_path(handle.clone(), url),
-                "a" => useless = !fix_anchor_path(handle.clone(), url),
                 _ => (),
             }
             dom::clean_attr("id", &mut attrs.borrow_mut());
@@ -345,24 +1113,43 @@ pub fn clean(
         ProcessingInstruction { .. } => unreachable!(),
     }
     let mut useless_nodes = vec![];
+    let mut unwrap_nodes = vec![];
     for (i, child) in handle.children.borrow().iter().enumerate() {
         let pid = id.join(i.to_string());
-        if clean(dom, pid.as_path(), child.clone(), url, candidates) {
+        if clean(
+            dom,
+            pid.as_path(),
+            child.clone(),
+            url,
+            page_url,
+            candidates,
+            options,
+        ) {
             useless_nodes.push(child.clone());
+        } else if is_unwrappable_link(child.clone(), options) {
+            unwrap_nodes.push(child.clone());
         }
     }
     for node in useless_nodes.iter() {
         dom.remove_from_parent(node);
     }
+    for node in unwrap_nodes.into_iter() {
+        unwrap_node(dom, node);
+    }
     if dom::is_empty(handle) {
         useless = true
     }
     useless
 }
 
-pub fn is_useless(id: &Path, handle: Handle, candidates: &BTreeMap<String, Candidate>) -> bool {
+pub fn is_useless(
+    id: &Path,
+    handle: Handle,
+    candidates: &BTreeMap<String, Candidate>,
+    options: &ExtractorOptions,
+) -> bool {
     let tag_name = &dom::get_tag_name(handle.clone()).unwrap_or_default();
-    let weight = get_class_weight(handle.clone());
+    let weight = get_class_weight(handle.clone(), options);
     let score = id
         .to_str()
         .and_then(|id| candidates.get(id))
@@ -384,7 +1171,7 @@ pub fn is_useless(id: &Path, handle: Handle, candidates: &BTreeMap<String, Candi
     dom::find_node(handle.clone(), "embed", &mut embed_nodes);
     let p_count = p_nodes.len();
     let img_count = img_nodes.len();
-    let li_count = li_nodes.len() as i32 - 100;
+    let li_count = li_nodes.len() as i32 - options.li_count_offset;
     let input_count = input_nodes.len();
     let embed_count = embed_nodes.len();
     let link_density = get_link_density(handle.clone());
@@ -400,13 +1187,13 @@ pub fn is_useless(id: &Path, handle: Handle, candidates: &BTreeMap<String, Candi
     if input_count as f32 > f32::floor(para_count as f32 / 3.0) {
         return true;
     }
-    if content_length < 25 && (img_count == 0 || img_count > 2) {
+    if content_length < options.min_content_length && (img_count == 0 || img_count > 2) {
         return true;
     }
-    if weight < 25.0 && link_density > 0.2 {
+    if weight < options.class_weight && link_density > options.max_link_density {
         return true;
     }
-    if (embed_count == 1 && content_length < 35) || embed_count > 1 {
+    if (embed_count == 1 && content_length < options.min_embed_content_length) || embed_count > 1 {
         return true;
     }
     false
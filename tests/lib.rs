@@ -1,7 +1,18 @@
+extern crate html5ever;
+extern crate markup5ever_rcdom;
 extern crate readability;
+extern crate regex;
 extern crate url;
 
+use html5ever::parse_document;
+use html5ever::tendril::stream::TendrilSink;
+use markup5ever_rcdom::RcDom;
+use readability::date::DateSource;
+use readability::options::ExtractorOptions;
+use regex::Regex;
+use std::collections::BTreeMap;
 use std::fs::File;
+use std::path::Path;
 use url::Url;
 
 #[test]
@@ -27,3 +38,664 @@ fn test_fix_img_links() {
     let product = readability::extractor::extract(&mut file, &url).unwrap();
     assert_eq!(product.content, "<!DOCTYPE html><html><head><title>This is title</title></head><body><p><img src=\"https://example.com/poop.png\"></p></body></html>");
 }
+
+#[test]
+fn test_extract_byline() {
+    let mut file = File::open("./data/byline.html").unwrap();
+    let url = Url::parse("https://example.com").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert_eq!(product.byline, Some("By Jane Doe".to_string()));
+    assert!(!product.content.contains("Jane Doe"));
+}
+
+#[test]
+fn test_extract_byline_from_rel_author() {
+    let mut file = File::open("./data/byline_rel.html").unwrap();
+    let url = Url::parse("https://example.com").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert_eq!(product.byline, Some("Jane Doe".to_string()));
+    assert!(!product.content.contains("Jane Doe"));
+}
+
+#[test]
+fn test_extract_byline_from_itemprop_author() {
+    let mut file = File::open("./data/byline_itemprop.html").unwrap();
+    let url = Url::parse("https://example.com").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert_eq!(product.byline, Some("Jane Doe".to_string()));
+    assert!(!product.content.contains("Jane Doe"));
+}
+
+#[test]
+fn test_extract_byline_from_meta() {
+    let mut file = File::open("./data/meta_author.html").unwrap();
+    let url = Url::parse("https://example.com").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert_eq!(product.byline, Some("Meta Author".to_string()));
+}
+
+#[test]
+fn test_extract_dates() {
+    let mut file = File::open("./data/dates.html").unwrap();
+    let url = Url::parse("https://example.com").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    let published = product.published.unwrap();
+    assert_eq!(published.date.to_rfc3339(), "2023-05-17T08:00:00+00:00");
+    assert_eq!(published.source, DateSource::JsonLd);
+    let modified = product.modified.unwrap();
+    assert_eq!(modified.date.to_rfc3339(), "2023-05-18T09:30:00+09:00");
+    assert_eq!(modified.source, DateSource::Meta);
+}
+
+#[test]
+fn test_extract_dates_from_url() {
+    let mut file = File::open("./data/title.html").unwrap();
+    let url = Url::parse("https://example.com/2021/03/04/story.html").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    let published = product.published.unwrap();
+    assert_eq!(published.date.to_rfc3339(), "2021-03-04T00:00:00+00:00");
+    assert_eq!(published.source, DateSource::Url);
+    assert!(product.modified.is_none());
+}
+
+#[test]
+fn test_extract_open_graph_metadata() {
+    let mut file = File::open("./data/open_graph.html").unwrap();
+    let url = Url::parse("https://example.com/articles/1").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert_eq!(product.title, "OpenGraph title");
+    assert_eq!(
+        product.lead_image,
+        Some("https://example.com/images/lead.png".to_string())
+    );
+    let metadata = product.metadata;
+    assert_eq!(
+        metadata.open_graph.description,
+        Some("OpenGraph description".to_string())
+    );
+    assert_eq!(
+        metadata.open_graph.site_name,
+        Some("Example Site".to_string())
+    );
+    assert_eq!(
+        metadata.twitter.card,
+        Some("summary_large_image".to_string())
+    );
+    assert_eq!(metadata.twitter.title, Some("Twitter title".to_string()));
+    assert_eq!(metadata.twitter.creator, Some("@example".to_string()));
+}
+
+#[test]
+fn test_extract_json_ld() {
+    let mut file = File::open("./data/json_ld.html").unwrap();
+    let url = Url::parse("https://example.com").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert_eq!(product.title, "JSON-LD headline");
+    assert_eq!(product.byline, Some("Jane Doe, John Roe".to_string()));
+    assert_eq!(
+        product.lead_image,
+        Some("https://example.com/ld.png".to_string())
+    );
+    assert_eq!(product.published.unwrap().source, DateSource::JsonLd);
+    let article = product.metadata.json_ld.unwrap();
+    assert_eq!(article.kind, "NewsArticle");
+    assert_eq!(article.publisher, Some("Example Publisher".to_string()));
+    assert_eq!(article.description, Some("JSON-LD description".to_string()));
+    assert_eq!(
+        article.article_body,
+        Some("Full body of the article.".to_string())
+    );
+}
+
+#[test]
+fn test_clean_title() {
+    use readability::extractor::clean_title;
+    let none: Vec<String> = vec![];
+    // the piece matching a heading wins
+    assert_eq!(
+        clean_title(
+            "Story headline | Site Name - Section",
+            &["Story headline".to_string()],
+            None
+        ),
+        "Story headline"
+    );
+    // the piece matching og:title wins
+    assert_eq!(
+        clean_title("Site Name » Story headline", &none, Some("Story headline")),
+        "Story headline"
+    );
+    // the last separated part is dropped
+    assert_eq!(
+        clean_title("A fairly long story headline | Site Name", &none, None),
+        "A fairly long story headline"
+    );
+    // the first part is dropped when the remainder is too short
+    assert_eq!(
+        clean_title("Site Name | A fairly long story headline", &none, None),
+        "A fairly long story headline"
+    );
+    // the part after the last colon is used
+    assert_eq!(
+        clean_title(
+            "Breaking News: Something really big happened today",
+            &none,
+            None
+        ),
+        "Something really big happened today"
+    );
+    // the part after the first colon is used when the last one is too short
+    assert_eq!(
+        clean_title("Interview: Jane Doe on writing: Part two", &none, None),
+        "Jane Doe on writing: Part two"
+    );
+    // the colon is kept when the prefix is long
+    assert_eq!(
+        clean_title(
+            "This is a very long prefix sentence: And the rest of it",
+            &none,
+            None
+        ),
+        "This is a very long prefix sentence: And the rest of it"
+    );
+    // a very short title is replaced by the only h1
+    assert_eq!(
+        clean_title(
+            "Home",
+            &["The actual headline of the article".to_string()],
+            None
+        ),
+        "The actual headline of the article"
+    );
+    // falls back to the original when the cleaned title is too short
+    assert_eq!(clean_title("Foo - Bar", &none, None), "Foo - Bar");
+}
+
+#[test]
+fn test_extract_clean_title() {
+    let mut file = File::open("./data/title_separator.html").unwrap();
+    let url = Url::parse("https://example.com").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert_eq!(product.title, "Story headline");
+}
+
+#[test]
+fn test_extract_excerpt() {
+    let mut file = File::open("./data/open_graph.html").unwrap();
+    let url = Url::parse("https://example.com").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert_eq!(product.excerpt, Some("OpenGraph description".to_string()));
+
+    let mut file = File::open("./data/excerpt.html").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert_eq!(
+        product.excerpt,
+        Some("This is the first substantive paragraph of the article. It keeps going for a while so that it is longer than the excerpt limit! And then it has a third sentence.".to_string())
+    );
+}
+
+#[test]
+fn test_truncate_at_sentence() {
+    use readability::extractor::truncate_at_sentence;
+    assert_eq!(
+        truncate_at_sentence("First sentence here. Second sentence here.", 30),
+        "First sentence here."
+    );
+    assert_eq!(
+        truncate_at_sentence("No sentence boundary in this text at all", 20),
+        "No sentence boundary…"
+    );
+    assert_eq!(
+        truncate_at_sentence("最初の文です。次の文です。", 10),
+        "最初の文です。"
+    );
+}
+
+#[test]
+fn test_extract_lead_image() {
+    let mut file = File::open("./data/lead_image.html").unwrap();
+    let url = Url::parse("https://example.com/articles/1").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert_eq!(
+        product.lead_image,
+        Some("https://example.com/images/large.jpg".to_string())
+    );
+
+    let mut file = File::open("./data/image_src.html").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert_eq!(
+        product.lead_image,
+        Some("https://example.com/articles/images/link.png".to_string())
+    );
+}
+
+#[test]
+fn test_extract_lead_image_with_unlikely_substrings() {
+    let mut file = File::open("./data/lead_image_words.html").unwrap();
+    let url = Url::parse("https://example.com").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert_eq!(
+        product.lead_image,
+        Some("https://example.com/img/silicone-bakeware.jpg".to_string())
+    );
+}
+
+#[test]
+fn test_extract_lang_and_dir() {
+    let mut file = File::open("./data/rtl.html").unwrap();
+    let url = Url::parse("https://example.com").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert_eq!(product.lang, Some("ar".to_string()));
+    assert_eq!(product.dir, Some("rtl".to_string()));
+    assert!(product.content.starts_with("<article dir=\"rtl\">"));
+
+    let mut file = File::open("./data/content_language.html").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert_eq!(product.lang, Some("ja".to_string()));
+    assert_eq!(product.dir, None);
+
+    // no candidate is found, so the attributes come from the html element
+    let mut file = File::open("./data/rtl_short.html").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert_eq!(product.lang, Some("he".to_string()));
+    assert_eq!(product.dir, Some("rtl".to_string()));
+}
+
+#[test]
+fn test_count_words() {
+    use readability::extractor::count_words;
+    assert_eq!(count_words("  Hello,   world!\n It's  here. "), 4);
+    assert_eq!(count_words("これは日本語です。"), 8);
+    assert_eq!(count_words("iPhone最新 モデル"), 6);
+}
+
+#[test]
+fn test_extract_reading_time() {
+    use std::time::Duration;
+    let mut file = File::open("./data/byline.html").unwrap();
+    let url = Url::parse("https://example.com").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert_eq!(product.word_count, 30);
+    assert_eq!(product.reading_time, Duration::from_secs(9));
+    assert_eq!(
+        product.estimate_reading_time(60, 500),
+        Duration::from_secs(30)
+    );
+    assert_eq!(product.char_count, 126);
+    assert_eq!(product.cjk_char_count, 0);
+
+    // CJK characters are read faster than words
+    let mut file = File::open("./data/japanese.html").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert_eq!(product.word_count, 61);
+    assert_eq!(product.cjk_char_count, 61);
+    assert_eq!(product.reading_time, Duration::from_secs(8));
+    assert_eq!(
+        product.estimate_reading_time(200, 200),
+        Duration::from_secs(19)
+    );
+}
+
+#[test]
+fn test_extract_canonical_url() {
+    let mut file = File::open("./data/canonical.html").unwrap();
+    let url = Url::parse("https://www.example.com/articles/1?utm_source=feed").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert_eq!(
+        product.canonical_url,
+        Some("https://www.example.com/articles/1".to_string())
+    );
+    assert_eq!(
+        product.amp_url,
+        Some("https://www.example.com/amp/articles/1".to_string())
+    );
+    assert_eq!(product.site_name, Some("example.com".to_string()));
+
+    let mut file = File::open("./data/open_graph.html").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert_eq!(product.canonical_url, None);
+    assert_eq!(product.site_name, Some("Example Site".to_string()));
+}
+
+#[test]
+fn test_extract_with_options() {
+    use std::time::Duration;
+    let url = Url::parse("https://example.com").unwrap();
+    let mut file = File::open("./data/byline.html").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert!(product.content.starts_with("<article>"));
+
+    let options = ExtractorOptions::builder()
+        .min_candidate_length(1000)
+        .excerpt_length(40)
+        .words_per_minute(60)
+        .build();
+    let mut file = File::open("./data/byline.html").unwrap();
+    let product = readability::extractor::extract_with_options(&mut file, &url, &options).unwrap();
+    assert!(product.content.starts_with("<!DOCTYPE html>"));
+    assert_eq!(
+        product.excerpt,
+        Some("This is the first paragraph of the…".to_string())
+    );
+    assert_eq!(
+        product.reading_time,
+        Duration::from_secs(product.word_count as u64)
+    );
+}
+
+#[test]
+fn test_extract_with_candidate_patterns() {
+    let url = Url::parse("https://example.com").unwrap();
+    let mut file = File::open("./data/newsletter.html").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert!(product.text.contains("Subscribe"));
+
+    let options = ExtractorOptions::builder()
+        .add_unlikely_candidate(Regex::new("newsletter").unwrap())
+        .char_threshold(0)
+        .build();
+    let mut file = File::open("./data/newsletter.html").unwrap();
+    let product = readability::extractor::extract_with_options(&mut file, &url, &options).unwrap();
+    assert!(!product.text.contains("Subscribe"));
+    assert!(product.text.contains("second paragraph"));
+}
+
+#[test]
+fn test_extract_retries_with_relaxed_heuristics() {
+    let url = Url::parse("https://example.com").unwrap();
+    let mut file = File::open("./data/unlikely.html").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert!(product.text.contains("first paragraph"));
+
+    let options = ExtractorOptions::builder().char_threshold(0).build();
+    let mut file = File::open("./data/unlikely.html").unwrap();
+    let product = readability::extractor::extract_with_options(&mut file, &url, &options).unwrap();
+    assert!(!product.text.contains("first paragraph"));
+}
+
+#[test]
+fn test_extract_merges_siblings() {
+    let mut file = File::open("./data/siblings.html").unwrap();
+    let url = Url::parse("https://example.com").unwrap();
+    let options = ExtractorOptions::builder().char_threshold(0).build();
+    let product = readability::extractor::extract_with_options(&mut file, &url, &options).unwrap();
+    assert!(product.content.starts_with("<div><div>"));
+    assert!(product.text.contains("first paragraph"));
+    assert!(product.text.contains("fifth paragraph"));
+    assert!(product.text.contains("Thanks for reading."));
+    assert!(!product.text.contains("Share this story"));
+}
+
+#[test]
+fn test_extract_converts_divs_to_paragraphs() {
+    let mut file = File::open("./data/div_paragraphs.html").unwrap();
+    let url = Url::parse("https://example.com").unwrap();
+    let options = ExtractorOptions::builder().char_threshold(0).build();
+    let product = readability::extractor::extract_with_options(&mut file, &url, &options).unwrap();
+    assert_eq!(
+        product.content,
+        "<div><p>This is the first paragraph of the article, written <em>without</em> any paragraph tags.</p><div><p>\n        This is the second paragraph of the article, followed by a quote.\n        </p><blockquote>Quoted text.</blockquote><p>\n        And this is the text after the quote, which is long enough to be scored.\n      </p></div><p><a href=\"https://example.com/archive\">More stories from the archive</a> on this topic.</p></div>"
+    );
+}
+
+fn candidate_scores(path: &str, options: &ExtractorOptions) -> Vec<(String, f32)> {
+    let mut file = File::open(path).unwrap();
+    let dom = parse_document(RcDom::default(), Default::default())
+        .from_utf8()
+        .read_from(&mut file)
+        .unwrap();
+    let mut candidates = BTreeMap::new();
+    let mut nodes = BTreeMap::new();
+    readability::scorer::find_candidates(
+        Path::new("/"),
+        dom.document.clone(),
+        &mut candidates,
+        &mut nodes,
+        options,
+    );
+    let mut scores = candidates
+        .values()
+        .map(|c| {
+            let name = readability::dom::get_attr("id", c.node.clone())
+                .or_else(|| readability::dom::get_tag_name(c.node.clone()))
+                .unwrap_or_default();
+            (name, c.score.get())
+        })
+        .collect::<Vec<_>>();
+    scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    scores
+}
+
+#[test]
+fn test_score_propagation_in_nested_layout() {
+    let scores = candidate_scores("./data/nested.html", &ExtractorOptions::default());
+    let names = scores.iter().map(|s| s.0.as_ref()).collect::<Vec<&str>>();
+    // The direct parent of the paragraphs wins, and the score decays per level
+    // up to the fifth ancestor, so `body` is never scored.
+    assert_eq!(names, vec!["inner", "wrapper", "grid", "layout", "outer"]);
+
+    let options = ExtractorOptions::builder().ancestor_levels(2).build();
+    let scores = candidate_scores("./data/nested.html", &options);
+    let names = scores.iter().map(|s| s.0.as_ref()).collect::<Vec<&str>>();
+    assert_eq!(names, vec!["inner", "wrapper"]);
+}
+
+#[test]
+fn test_score_propagation_in_nested_sections() {
+    let scores = candidate_scores("./data/nested_sections.html", &ExtractorOptions::default());
+    // The wrapper collects half of the score of every section's paragraphs,
+    // which beats each section on its own.
+    assert_eq!(scores[0].0, "wrapper");
+    assert!(scores.iter().all(|s| s.0 != "html"));
+}
+
+#[test]
+fn test_extract_promotes_common_ancestor() {
+    // The sections are nested deeper than `ancestor_levels`, so their common
+    // ancestor is only reached by comparing the top candidates.
+    let url = Url::parse("https://example.com").unwrap();
+    let options = ExtractorOptions::builder().char_threshold(0).build();
+    let mut file = File::open("./data/sections.html").unwrap();
+    let product = readability::extractor::extract_with_options(&mut file, &url, &options).unwrap();
+    assert!(product.text.contains("first section"));
+    assert!(product.text.contains("fourth section"));
+
+    let options = ExtractorOptions::builder()
+        .char_threshold(0)
+        .top_candidates(1)
+        .build();
+    let mut file = File::open("./data/sections.html").unwrap();
+    let product = readability::extractor::extract_with_options(&mut file, &url, &options).unwrap();
+    assert!(product.text.contains("first section"));
+    assert!(!product.text.contains("fourth section"));
+}
+
+#[test]
+fn test_extract_with_zero_candidate_counts() {
+    // both counts are treated as 1, so the article is neither replaced by the
+    // document nor promoted to its parent
+    let url = Url::parse("https://example.com").unwrap();
+    for options in [
+        ExtractorOptions::builder().top_candidates(0).build(),
+        ExtractorOptions::builder()
+            .min_alternative_candidates(0)
+            .build(),
+    ]
+    .iter()
+    {
+        let mut file = File::open("./data/single_article.html").unwrap();
+        let product =
+            readability::extractor::extract_with_options(&mut file, &url, options).unwrap();
+        assert!(product.content.starts_with("<article>"));
+    }
+}
+
+#[test]
+fn test_is_probably_readerable() {
+    let options = ExtractorOptions::default();
+    let parse = |path: &str| {
+        let mut file = File::open(path).unwrap();
+        parse_document(RcDom::default(), Default::default())
+            .from_utf8()
+            .read_from(&mut file)
+            .unwrap()
+    };
+    let (readerable, score) =
+        readability::scorer::is_probably_readerable(&parse("./data/readerable.html"), &options);
+    assert!(readerable);
+    // only the two visible, likely paragraphs outside of lists count
+    assert!(score > 20.0 && score < 30.0);
+    let (readerable, score) =
+        readability::scorer::is_probably_readerable(&parse("./data/title.html"), &options);
+    assert!(!readerable);
+    assert_eq!(score, 0.0);
+}
+
+#[test]
+fn test_extract_skips_hidden_elements() {
+    let mut file = File::open("./data/hidden.html").unwrap();
+    let url = Url::parse("https://example.com").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert!(product.text.contains("This paragraph is visible"));
+    assert!(!product.content.contains("Hidden"));
+    assert!(product.content.contains("https://example.com/math.svg"));
+}
+
+#[test]
+fn test_extract_removes_role_based_boilerplate() {
+    let mut file = File::open("./data/roles.html").unwrap();
+    let url = Url::parse("https://example.com").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert!(product.text.contains("The article itself"));
+    assert!(!product.text.contains("Sports"));
+    assert!(!product.text.contains("newsletter"));
+    assert!(!product.text.contains("next story"));
+    assert!(!product.text.contains("Related"));
+}
+
+#[test]
+fn test_extract_lazy_images() {
+    let mut file = File::open("./data/lazy_images.html").unwrap();
+    let url = Url::parse("https://example.com").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert!(product
+        .content
+        .contains("src=\"https://example.com/images/first.jpg\""));
+    assert!(product
+        .content
+        .contains("src=\"https://cdn.example.com/second.jpg\""));
+    assert!(product
+        .content
+        .contains("src=\"https://example.com/images/third.jpg\""));
+    assert!(product
+        .content
+        .contains("srcset=\"https://example.com/images/third-2x.jpg 2x\""));
+    assert!(!product.content.contains("blur.jpg"));
+    assert!(!product.content.contains("src=\"data:"));
+    assert!(!product.content.contains("alt=\"fourth\""));
+}
+
+#[test]
+fn test_parse_srcset() {
+    use readability::scorer::parse_srcset;
+    let pair = |src: &str, descriptor: &str| (src.to_string(), descriptor.to_string());
+    assert_eq!(
+        parse_srcset("a.jpg 1x, b.jpg 2x"),
+        vec![pair("a.jpg", "1x"), pair("b.jpg", "2x")]
+    );
+    assert_eq!(
+        parse_srcset("/w_100,h_100/a.jpg 100w,/w_200,h_200/b.jpg 200w"),
+        vec![
+            pair("/w_100,h_100/a.jpg", "100w"),
+            pair("/w_200,h_200/b.jpg", "200w")
+        ]
+    );
+    assert_eq!(
+        parse_srcset("a.jpg, b.jpg 2x"),
+        vec![pair("a.jpg", ""), pair("b.jpg", "2x")]
+    );
+}
+
+#[test]
+fn test_fix_media_links() {
+    let mut file = File::open("./data/media.html").unwrap();
+    let url = Url::parse("https://example.com/articles/1").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    for fixed in [
+        "srcset=\"https://example.com/img/photo.webp 1x, https://example.com/img/photo@2x.webp 2x\"",
+        "src=\"https://example.com/articles/img/photo.jpg\"",
+        "srcset=\"https://example.com/img/w_100,h_100/photo.jpg 100w, https://example.com/img/w_200,h_200/photo.jpg 200w\"",
+        "src=\"https://example.com/media/clip.mp4\"",
+        "poster=\"https://example.com/media/poster.jpg\"",
+        "src=\"https://example.com/media/clip.vtt\"",
+        "src=\"https://example.com/articles/media/interview.mp3\"",
+    ]
+    .iter()
+    {
+        assert!(product.content.contains(fixed), "missing {}", fixed);
+    }
+}
+
+#[test]
+fn test_fix_links_with_base_href() {
+    let mut file = File::open("./data/base.html").unwrap();
+    let url = Url::parse("https://example.com/articles/1").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert_eq!(product.metadata.base, Some("/blog/".to_string()));
+    assert!(product
+        .content
+        .contains("href=\"https://example.com/blog/previous-post\""));
+    assert!(product
+        .content
+        .contains("src=\"https://example.com/blog/images/chart.png\""));
+}
+
+#[test]
+fn test_fix_special_links() {
+    let url = Url::parse("http://example.com/articles/1").unwrap();
+    let mut file = File::open("./data/links.html").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert!(product
+        .content
+        .contains("href=\"http://cdn.example.com/assets/report.pdf\""));
+    assert!(!product.content.contains("javascript:"));
+    assert!(product.content.contains("Some links open a popup that"));
+    assert!(product
+        .content
+        .contains("href=\"mailto:editor@example.com\""));
+    assert!(product.content.contains("href=\"tel:+15555550100\""));
+    assert!(product
+        .content
+        .contains("href=\"http://example.com/articles/1#methodology\""));
+
+    let options = ExtractorOptions::builder()
+        .absolutize_fragment_links(false)
+        .build();
+    let mut file = File::open("./data/links.html").unwrap();
+    let product = readability::extractor::extract_with_options(&mut file, &url, &options).unwrap();
+    assert!(!product.content.contains("#methodology"));
+    assert!(product.content.contains("The methodology is described"));
+}
+
+#[test]
+fn test_extract_unwraps_noscript_images() {
+    let mut file = File::open("./data/noscript.html").unwrap();
+    let url = Url::parse("https://example.com").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert!(product.content.contains(
+        "<img src=\"https://example.com/images/photo.jpg\" alt=\"The real photo\" data-id=\"42\">"
+    ));
+    assert!(!product.content.contains("data:image/gif"));
+    assert!(!product.content.contains("noscript"));
+}
+
+#[test]
+fn test_extract_keeps_real_image_before_noscript() {
+    let mut file = File::open("./data/noscript_pixel.html").unwrap();
+    let url = Url::parse("https://example.com").unwrap();
+    let product = readability::extractor::extract(&mut file, &url).unwrap();
+    assert!(product
+        .content
+        .contains("<img src=\"https://example.com/real.jpg\" alt=\"real\">"));
+    assert!(!product.content.contains("pixel.gif"));
+}